@@ -0,0 +1,215 @@
+use ratatui::{prelude::{Backend, Rect}, Frame, widgets::{Block, Borders, Clear, Paragraph}};
+use rusqlite::types::Value;
+
+use super::format::size;
+use super::popup::centered_rect;
+use super::theme::Theme;
+
+/// Bytes shown on a line of the dump
+const WIDTH: usize = 16;
+
+/// Read a protobuf varint, returning the value and its length
+fn varint(b: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, byte) in b.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Check if the bytes can be read as protobuf fields, there is no
+/// magic number so this only says that it might be protobuf
+fn is_protobuf(mut b: &[u8]) -> bool {
+    if b.len() < 2 {
+        return false;
+    }
+    while !b.is_empty() {
+        let Some((key, n)) = varint(b) else { return false };
+        if key >> 3 == 0 {
+            return false;
+        }
+        b = &b[n..];
+        let skip = match key & 7 {
+            0 => match varint(b) {
+                Some((_, n)) => n,
+                None => return false,
+            },
+            1 => 8,
+            2 => match varint(b) {
+                Some((len, n)) => n.saturating_add(len as usize),
+                None => return false,
+            },
+            5 => 4,
+            _ => return false,
+        };
+        if skip > b.len() {
+            return false;
+        }
+        b = &b[skip..];
+    }
+    true
+}
+
+fn be32(b: &[u8]) -> u32 {
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+/// Guess what kind of data a blob is from its first bytes
+pub fn kind(b: &[u8]) -> Option<String> {
+    let kind = if b.starts_with(b"\x89PNG\r\n\x1a\n") {
+        if b.len() >= 24 && &b[12..16] == b"IHDR" {
+            return Some(format!("PNG image {}x{}", be32(&b[16..20]), be32(&b[20..24])));
+        }
+        "PNG image"
+    } else if b.starts_with(&[0xff, 0xd8, 0xff]) {
+        "JPEG image"
+    } else if b.starts_with(b"GIF87a") || b.starts_with(b"GIF89a") {
+        if b.len() >= 10 {
+            let w = u16::from_le_bytes([b[6], b[7]]);
+            let h = u16::from_le_bytes([b[8], b[9]]);
+            return Some(format!("GIF image {}x{}", w, h));
+        }
+        "GIF image"
+    } else if b.len() >= 12 && b.starts_with(b"RIFF") && &b[8..12] == b"WEBP" {
+        "WebP image"
+    } else if b.starts_with(b"%PDF") {
+        "PDF document"
+    } else if b.starts_with(&[0x1f, 0x8b]) {
+        "gzip data"
+    } else if b.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        "zstd data"
+    } else if b.starts_with(b"BZh") {
+        "bzip2 data"
+    } else if b.starts_with(b"PK\x03\x04") {
+        "zip archive"
+    } else if b.starts_with(b"SQLite format 3\0") {
+        "SQLite database"
+    } else if std::str::from_utf8(b).is_ok() {
+        "UTF-8 text"
+    } else if is_protobuf(b) {
+        "protobuf?"
+    } else {
+        return None;
+    };
+    Some(kind.to_owned())
+}
+
+/// A line of a hex dump, starting at offset
+pub fn dump_line(b: &[u8], offset: usize) -> String {
+    let chunk = &b[offset..b.len().min(offset + WIDTH)];
+    let mut hex = String::new();
+    for i in 0..WIDTH {
+        if i == WIDTH / 2 {
+            hex.push(' ');
+        }
+        match chunk.get(i) {
+            Some(byte) => hex.push_str(&format!("{:02x} ", byte)),
+            None => hex.push_str("   "),
+        }
+    }
+    let ascii: String = chunk.iter()
+        .map(|&c| if c.is_ascii_graphic() || c == b' ' { c as char } else { '.' })
+        .collect();
+    format!("{:08x}  {} |{}|", offset, hex, ascii)
+}
+
+/// A popup with a hex dump of a blob
+pub struct BlobView {
+    pub data: Vec<u8>,
+    /// The cell the blob is from, (absolute row, column)
+    pub cell: (usize, usize),
+    /// The first line shown
+    scroll: usize,
+    /// Lines that fit in the popup, set when rendering
+    height: usize,
+}
+
+impl BlobView {
+    /// View a blob, NULL is an empty blob so a file can be loaded into it
+    pub fn new(value: &Value, cell: (usize, usize)) -> Option<Self> {
+        let data = match value {
+            Value::Blob(b) => b.clone(),
+            Value::Null => Vec::new(),
+            _ => return None,
+        };
+        Some(BlobView {
+            data,
+            cell,
+            scroll: 0,
+            height: 1,
+        })
+    }
+
+    fn lines(&self) -> usize {
+        self.data.len().div_ceil(WIDTH)
+    }
+
+    pub fn down(&mut self, n: usize) {
+        self.scroll = (self.scroll + n).min(self.lines().saturating_sub(1));
+    }
+
+    pub fn up(&mut self, n: usize) {
+        self.scroll = self.scroll.saturating_sub(n);
+    }
+
+    pub fn page_down(&mut self) {
+        self.down(self.height);
+    }
+
+    pub fn page_up(&mut self) {
+        self.up(self.height);
+    }
+
+    pub fn first(&mut self) {
+        self.scroll = 0;
+    }
+
+    pub fn last(&mut self) {
+        self.scroll = self.lines().saturating_sub(self.height);
+    }
+
+    pub fn title(&self) -> String {
+        match kind(&self.data) {
+            Some(kind) => format!("Blob {}, {}", size(self.data.len()), kind),
+            None => format!("Blob {}", size(self.data.len())),
+        }
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, theme: &Theme) {
+        let area: Rect = centered_rect(80, 80, f.size());
+        self.height = area.height.saturating_sub(2).max(1) as usize;
+        let text: Vec<String> = (self.scroll..self.lines())
+            .take(self.height)
+            .map(|line| dump_line(&self.data, line * WIDTH))
+            .collect();
+        let block = Block::default()
+            .title(self.title())
+            .title_style(theme.header)
+            .borders(Borders::ALL);
+        let dump = Paragraph::new(text.join("\n"))
+            .style(theme.blob)
+            .block(block);
+        f.render_widget(Clear, area);
+        f.render_widget(dump, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dump_line, kind};
+
+    #[test]
+    fn test_kind() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 2, 0x80, 0, 0, 1, 0xe0]);
+        assert_eq!(kind(&png).unwrap(), "PNG image 640x480");
+        assert_eq!(kind(&[0x1f, 0x8b, 8, 0]).unwrap(), "gzip data");
+        // field 1 varint 150, field 2 string "hi"
+        assert_eq!(kind(&[0x08, 0x96, 0x01, 0x12, 0x02, b'h', b'i']).unwrap(), "protobuf?");
+        assert_eq!(kind(&[0xff, 0xfe, 0x00]), None);
+        assert_eq!(dump_line(b"ab\x00", 0), format!("00000000  61 62 00 {}|ab.|", " ".repeat(3 * 13 + 2)));
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use rusqlite::types::Value;
+
+use super::export::{base64, Exporter, Format};
+
+/// The escape sequence that asks the terminal to put text on the clipboard,
+/// it works over ssh but not every terminal supports it
+pub fn osc52(text: &str) -> String {
+    let seq = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    // tmux only passes it on to the terminal wrapped in its own sequence
+    match std::env::var_os("TMUX") {
+        Some(_) => format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b")),
+        None => seq,
+    }
+}
+
+/// Copy text to the clipboard with OSC 52, and also by piping it to command
+/// (like xclip -selection clipboard, wl-copy or pbcopy) for terminals without it
+pub fn copy(text: &str, command: Option<&str>) -> io::Result<()> {
+    let mut out = io::stdout();
+    out.write_all(osc52(text).as_bytes())?;
+    out.flush()?;
+    let Some(command) = command else {
+        return Ok(());
+    };
+    let mut args = command.split_whitespace();
+    let program = args.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Empty clipboard command"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed with {}", program, status)));
+    }
+    Ok(())
+}
+
+/// Rows as text in an export format, a single json row is an object and not an array
+pub fn rows(format: Format, table: &str, columns: &[String], rows: &[Vec<Value>]) -> io::Result<String> {
+    let format = match format {
+        Format::Json if rows.len() == 1 => Format::Ndjson,
+        format => format,
+    };
+    let mut buf = Vec::new();
+    let mut exporter = Exporter::new(&mut buf, format, table, columns)?;
+    for row in rows {
+        exporter.row(row)?;
+    }
+    exporter.finish()?;
+    let text = String::from_utf8_lossy(&buf);
+    Ok(text.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::types::Value;
+
+    use super::{osc52, rows};
+    use crate::export::Format;
+
+    #[test]
+    fn test_yank() {
+        if std::env::var_os("TMUX").is_none() {
+            assert_eq!(osc52("hi"), "\x1b]52;c;aGk=\x07");
+        }
+        let columns = vec!["id".to_owned(), "name".to_owned()];
+        let row = vec![vec![Value::Integer(1), Value::Text("a\tb".to_owned())]];
+        assert_eq!(rows(Format::Tsv, "t", &columns, &row).unwrap(), "id\tname\n1\ta\\tb");
+        assert_eq!(rows(Format::Json, "t", &columns, &row).unwrap(), "{\"id\":1,\"name\":\"a\\tb\"}");
+        assert_eq!(rows(Format::Sql, "t", &columns, &row).unwrap(), "INSERT INTO \"t\" (\"id\", \"name\") VALUES (1, 'a\tb');");
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::{HashMap, HashSet};
+
+use ratatui::{prelude::{Backend, Rect}, Frame, widgets::{Block, Borders, Clear, List, ListItem, ListState}};
+use rusqlite::Connection;
+
+use super::schema::table_info;
+use super::sql::{is_keyword, quote, TableName, FUNCTIONS, KEYWORDS};
+use super::theme::Theme;
+
+/// The most candidates shown at once
+const HEIGHT: usize = 8;
+
+/// Names sql is completed with, the tables and views of every database and their columns
+#[derive(Default)]
+pub struct Completions {
+    /// Named like the tabs, other.table in attached databases
+    tables: Vec<String>,
+    /// The columns of every table, by its lowercase name
+    columns: HashMap<String, Vec<String>>,
+}
+
+/// Split sql into words, keeping qualified names like s.t together and commas and parentheses apart
+fn words(sql: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in sql.chars() {
+        if c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | '"' | '`') {
+            word.push(c);
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if matches!(c, ',' | '(' | ')' | ';') {
+            words.push(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// The tables named after FROM, JOIN, UPDATE and INTO, with their alias
+pub fn tables_in(sql: &str) -> Vec<(String, Option<String>)> {
+    let words = words(sql);
+    let is_name = |w: &str| w.starts_with(|c: char| c.is_alphabetic() || matches!(c, '_' | '"' | '`')) && !is_keyword(w);
+    let unquote = |w: &str| w.replace(['"', '`'], "");
+    let mut tables = Vec::new();
+    // in a FROM clause a comma is followed by another table
+    let mut from = false;
+    let mut i = 0;
+    while i < words.len() {
+        let upper = words[i].to_uppercase();
+        i += 1;
+        let table_next = match upper.as_str() {
+            "FROM" | "JOIN" => {
+                from = true;
+                true
+            }
+            "UPDATE" | "INTO" => {
+                from = false;
+                true
+            }
+            "," => from,
+            "WHERE" | "GROUP" | "ORDER" | "HAVING" | "LIMIT" | "WINDOW" | "UNION" | "EXCEPT" | "INTERSECT"
+                | "SELECT" | "SET" | "VALUES" | ")" | ";" => {
+                from = false;
+                false
+            }
+            _ => false,
+        };
+        if !table_next || !words.get(i).is_some_and(|w| is_name(w)) {
+            continue;
+        }
+        let table = unquote(&words[i]);
+        i += 1;
+        if words.get(i).is_some_and(|w| w.eq_ignore_ascii_case("AS")) {
+            i += 1;
+        }
+        let alias = match words.get(i) {
+            Some(w) if is_name(w) => {
+                i += 1;
+                Some(unquote(w))
+            }
+            _ => None,
+        };
+        tables.push((table, alias));
+    }
+    tables
+}
+
+/// The word being typed at the end of text, empty inside a string
+pub fn word(text: &str) -> &str {
+    if text.matches('\'').count() % 2 == 1 {
+        return "";
+    }
+    let start = text.rfind(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '$' | '.')))
+        .map_or(0, |i| i + text[i..].chars().next().map_or(1, char::len_utf8));
+    &text[start..]
+}
+
+/// Keywords and functions in the case of what is typed
+fn cased(name: &str, word: &str) -> String {
+    match word.chars().any(char::is_lowercase) {
+        true => name.to_lowercase(),
+        false => name.to_uppercase(),
+    }
+}
+
+impl Completions {
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        let schemas: Vec<String> = {
+            let mut stmt = conn.prepare("PRAGMA database_list")?;
+            let rows = stmt.query_map([], |r| r.get(1))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let mut completions = Completions::default();
+        for schema in schemas {
+            let sql = format!(
+                "SELECT name FROM {}.sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%'",
+                quote(&schema)
+            );
+            let names: Vec<String> = {
+                let mut stmt = conn.prepare(&sql)?;
+                let rows = stmt.query_map([], |r| r.get(0))?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+            for name in names {
+                let table = TableName::new(Some(schema.as_str()).filter(|s| *s != "main"), &name);
+                // a view of a dropped table has no columns to read, its name is still completed
+                let columns = table_info(conn, &table).map_or(Vec::new(), |info| info.into_iter().map(|c| c.name).collect());
+                completions.columns.insert(table.to_string().to_lowercase(), columns);
+                completions.tables.push(table.to_string());
+            }
+        }
+        Ok(completions)
+    }
+
+    fn columns(&self, table: &str) -> Option<&Vec<String>> {
+        let table = table.to_lowercase();
+        self.columns.get(&table).or_else(|| self.columns.get(table.trim_start_matches("main.")))
+    }
+
+    /// What word can be completed to, columns come from the tables in sql
+    pub fn candidates(&self, sql: &str, word: &str) -> Vec<String> {
+        if word.is_empty() {
+            return Vec::new();
+        }
+        let tables = tables_in(sql);
+        let mut found: Vec<String> = Vec::new();
+        if let Some((qualifier, _)) = word.rsplit_once('.') {
+            // a column of a table or alias, or a table of an attached database
+            let table = tables.iter()
+                .find(|(t, alias)| alias.as_ref().unwrap_or(t).eq_ignore_ascii_case(qualifier))
+                .map_or(qualifier, |(t, _)| t.as_str());
+            let columns = self.columns(table).into_iter().flatten();
+            found.extend(columns.map(|c| format!("{}.{}", qualifier, c)));
+            found.extend(self.tables.iter().cloned());
+        } else {
+            for (table, _) in tables.iter() {
+                found.extend(self.columns(table).into_iter().flatten().cloned());
+            }
+            found.extend(self.tables.iter().cloned());
+            found.extend(FUNCTIONS.iter().map(|f| cased(f, word)));
+            found.extend(KEYWORDS.iter().map(|k| cased(k, word)));
+        }
+        let lower = word.to_lowercase();
+        let mut seen = HashSet::new();
+        found.retain(|c| {
+            let c = c.to_lowercase();
+            c.starts_with(&lower) && c != lower && seen.insert(c)
+        });
+        found
+    }
+}
+
+/// The candidates for the word before the cursor, shown in a list under it
+pub struct Completion {
+    pub word: String,
+    pub items: Vec<String>,
+    selected: usize,
+}
+
+impl Completion {
+    /// Complete the end of before, None if there is nothing to complete
+    pub fn new(completions: &Completions, sql: &str, before: &str) -> Option<Self> {
+        let word = word(before);
+        let items = completions.candidates(sql, word);
+        if items.is_empty() {
+            return None;
+        }
+        Some(Completion {
+            word: word.to_owned(),
+            items,
+            selected: 0,
+        })
+    }
+
+    pub fn selected(&self) -> &str {
+        &self.items[self.selected]
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    pub fn prev(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(self.items.len() - 1);
+    }
+
+    /// Draw the list under the cursor at (x, y), or over it if there is no room
+    pub fn render<B: Backend>(&self, f: &mut Frame<B>, cursor: (u16, u16), theme: &Theme) {
+        let screen = f.size();
+        let width = (self.items.iter().map(|i| i.chars().count()).max().unwrap_or(0) as u16 + 2).min(screen.width);
+        let height = self.items.len().min(HEIGHT) as u16 + 2;
+        // the list starts where the word does
+        let x = cursor.0.saturating_sub(self.word.chars().count() as u16).min(screen.width - width);
+        let y = match cursor.1 + 1 + height <= screen.height {
+            true => cursor.1 + 1,
+            false => cursor.1.saturating_sub(height),
+        };
+        let area = Rect::new(x, y, width, height.min(screen.height));
+        let items: Vec<ListItem> = self.items.iter().map(|i| ListItem::new(i.as_str())).collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL))
+            .highlight_style(theme.hlrow);
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::{tables_in, word, Completions};
+
+    #[test]
+    fn test_candidates() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE users(id, name, nick); CREATE TABLE orders(id, user, note);
+            CREATE TABLE gone(a); CREATE VIEW broken AS SELECT a FROM gone; DROP TABLE gone").unwrap();
+        let completions = Completions::load(&conn).unwrap();
+        let sql = "SELECT u.na FROM users AS u JOIN orders o ON o.user = u.id, log WHERE n";
+        assert_eq!(tables_in(sql), [
+            ("users".to_owned(), Some("u".to_owned())),
+            ("orders".to_owned(), Some("o".to_owned())),
+            ("log".to_owned(), None),
+        ]);
+        assert_eq!(word("SELECT u.na"), "u.na");
+        assert_eq!(word("SELECT 'a b"), "");
+        assert_eq!(completions.candidates(sql, "u.n"), ["u.name", "u.nick"]);
+        assert_eq!(completions.candidates(sql, "n"), ["name", "nick", "note", "nth_value", "ntile", "nullif", "natural", "no", "not", "nothing", "notnull", "null", "nulls"]);
+        assert_eq!(completions.candidates("SELECT * FROM ", "OR"), ["orders", "ORDER"]);
+        assert!(completions.candidates("SELECT * FROM ", "").is_empty());
+        assert_eq!(completions.candidates("SELECT * FROM ", "bro"), ["broken"]);
+    }
+}
@@ -1,8 +1,8 @@
 use std::{collections::HashMap, fmt::Display, convert::TryInto};
 
-use qb::error::ConvertError;
-use ratatui::style::Color;
-use serde::{Deserialize, Serialize, Serializer, ser::SerializeTuple};
+use qb::{error::ConvertError, saved::SavedQuery, theme::Theme};
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
 use crossterm::event::{KeyCode, ModifierKeyCode, KeyEvent, KeyEventState, KeyEventKind, KeyModifiers};
 use tui_input::InputRequest;
 
@@ -26,44 +26,138 @@ enum ColorDef{
     LightMagenta,
     LightCyan,
     White,
-    #[serde(serialize_with = "ColorDef::ser_rgb")]
     Rgb(u8, u8, u8),
     Indexed(u8),
 }
 
-impl ColorDef {
-    fn ser_rgb<S>(r: &u8, g: &u8, b: &u8, s: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer {
-            let mut tup = s.serialize_tuple(3)?;
-            tup.serialize_element(r)?;
-            tup.serialize_element(g)?;
-            tup.serialize_element(b)?;
-            tup.end()
+/// An optional color, so it can be left out of a style
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(transparent)]
+struct ColorOpt(#[serde(with = "ColorDef")] Color);
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+enum ModifierDef {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    SlowBlink,
+    RapidBlink,
+    Reversed,
+    Hidden,
+    CrossedOut,
+}
+
+impl From<ModifierDef> for Modifier {
+    fn from(m: ModifierDef) -> Self {
+        match m {
+            ModifierDef::Bold => Modifier::BOLD,
+            ModifierDef::Dim => Modifier::DIM,
+            ModifierDef::Italic => Modifier::ITALIC,
+            ModifierDef::Underlined => Modifier::UNDERLINED,
+            ModifierDef::SlowBlink => Modifier::SLOW_BLINK,
+            ModifierDef::RapidBlink => Modifier::RAPID_BLINK,
+            ModifierDef::Reversed => Modifier::REVERSED,
+            ModifierDef::Hidden => Modifier::HIDDEN,
+            ModifierDef::CrossedOut => Modifier::CROSSED_OUT,
+        }
     }
 }
 
+/// A style in the config, replaces the style of the theme
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct StyleDef {
+    fg: Option<ColorOpt>,
+    bg: Option<ColorOpt>,
+    modifiers: Vec<ModifierDef>,
+}
+
+impl From<&StyleDef> for Style {
+    fn from(def: &StyleDef) -> Self {
+        let mut style = Style::default();
+        if let Some(ColorOpt(fg)) = def.fg {
+            style = style.fg(fg);
+        }
+        if let Some(ColorOpt(bg)) = def.bg {
+            style = style.bg(bg);
+        }
+        def.modifiers.iter().fold(style, |style, m| style.add_modifier((*m).into()))
+    }
+}
+
+/// The parts of the ui that can be styled
+#[derive(Serialize, Deserialize, Hash, Eq, PartialEq, Clone, Copy)]
+pub enum Element {
+    Tab,
+    View,
+    HlTab,
+    Header,
+    HlRow,
+    HlCell,
+    Null,
+    Number,
+    Blob,
+    Dirty,
+    Matched,
+    Error,
+    Input,
+    Group,
+    Chip,
+    Visual,
+    Keyword,
+    String,
+    Comment,
+}
+
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Colors {
-    // maybe change to a style
-    #[serde(with = "ColorDef")]
-    tabcolor: Color,
-    #[serde(with = "ColorDef")]
-    hltab: Color,
-    #[serde(with = "ColorDef")]
-    headers: Color,
-    #[serde(with = "ColorDef")]
-    hlrow: Color,
+    /// One of the built-in themes: default, light or mono
+    pub theme: String,
+    /// Styles that replace the ones of the theme
+    pub styles: HashMap<Element, StyleDef>,
 }
 
 impl Default for Colors {
     fn default() -> Self { 
         Self { 
-            tabcolor: Color::Yellow,
-            hltab: Color::Yellow,
-            headers: Color::Yellow,
-            hlrow: Color::Yellow,
+            theme: "default".to_owned(),
+            styles: HashMap::new(),
+        }
+    }
+}
+
+impl Colors {
+    /// The named theme with the styles of the config
+    pub fn theme(&self) -> Result<Theme, ConvertError> {
+        let mut theme = Theme::named(&self.theme)
+            .ok_or_else(|| ConvertError::ThemeError(self.theme.clone()))?;
+        for (element, def) in self.styles.iter() {
+            let style = match element {
+                Element::Tab => &mut theme.tab,
+                Element::View => &mut theme.view,
+                Element::HlTab => &mut theme.hltab,
+                Element::Header => &mut theme.header,
+                Element::HlRow => &mut theme.hlrow,
+                Element::HlCell => &mut theme.hlcell,
+                Element::Null => &mut theme.null,
+                Element::Number => &mut theme.number,
+                Element::Blob => &mut theme.blob,
+                Element::Dirty => &mut theme.dirty,
+                Element::Matched => &mut theme.matched,
+                Element::Error => &mut theme.error,
+                Element::Input => &mut theme.input,
+                Element::Group => &mut theme.group,
+                Element::Chip => &mut theme.chip,
+                Element::Visual => &mut theme.visual,
+                Element::Keyword => &mut theme.keyword,
+                Element::String => &mut theme.string,
+                Element::Comment => &mut theme.comment,
+            };
+            *style = def.into();
         }
+        Ok(theme)
     }
 }
 
@@ -80,9 +174,36 @@ pub enum MainAction {
     Zoom,
     InputCurrent(String),
     Input(String),
+    /// Write sql for query or exec in the editor, a query starts from the one of the tab
+    Editor(String),
     Quit,
     Search,
     Reload,
+    Edit,
+    Commit,
+    Rollback,
+    NextMatch,
+    PrevMatch,
+    Schema,
+    Wider,
+    Narrower,
+    Sort,
+    SortAdd,
+    Filter,
+    FilterValue,
+    Exclude,
+    Unfilter,
+    ClearFilters,
+    Yank,
+    /// Yank the selected row as tsv, json or sql
+    YankRow(String),
+    VisualLine,
+    VisualBlock,
+    Insert,
+    Duplicate,
+    Delete,
+    /// Pick a query saved in the config to run
+    SavedQueries,
     // ClearError,
 }
 
@@ -93,6 +214,100 @@ pub enum ZoomAction {
     ZoomOut,
     Next,
     Prev,
+    Blob,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum BlobAction {
+    Back,
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    First,
+    Last,
+    Save,
+    Load,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum VisualAction {
+    Back,
+    Next,
+    Prev,
+    Hnext,
+    Hprev,
+    First,
+    Last,
+    /// Select whole rows, or leave visual mode if they already are
+    Line,
+    /// Select a block of cells, or leave visual mode if it already is
+    Block,
+    /// Yank the selection as tsv, json or sql
+    Yank(String),
+    /// A command on the selection, like export
+    Input(String),
+    Delete,
+    /// Set every selected cell to the same value
+    Set,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum FormAction {
+    Back,
+    Next,
+    Prev,
+    Submit,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum EditorAction {
+    /// Run the sql
+    Submit,
+    Leave,
+    Newline,
+    /// Replace the word before the cursor with the selected completion, indent if there is none
+    Complete,
+    NextCompletion,
+    PrevCompletion,
+    Indent,
+    Unindent,
+    Left,
+    Right,
+    /// On the first line, the sql run before
+    Up,
+    /// On the last line, the sql run after
+    Down,
+    Home,
+    End,
+    DeletePrevChar,
+    DeleteNextChar,
+    HistorySearch,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum PickerAction {
+    Back,
+    Next,
+    Prev,
+    Pick,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum SchemaAction {
+    Back,
+    Next,
+    Prev,
+    Expand,
+    Collapse,
+    Toggle,
+    Open,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ConfirmAction {
+    Yes,
+    No,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -109,6 +324,16 @@ pub enum InputAction {
     DeleteNextWord,
     DeleteLine,
     DeleteTillEnd,
+    ToggleCase,
+    ToggleRegex,
+    /// Replace the word before the cursor with the selected completion
+    Complete,
+    NextCompletion,
+    PrevCompletion,
+    /// Go back and forth through the query and exec commands that were run
+    HistoryPrev,
+    HistoryNext,
+    HistorySearch,
     Enter,
     Leave,
 }
@@ -130,6 +355,14 @@ impl TryInto<InputRequest> for &InputAction {
             InputAction::DeleteNextWord => Ok(InputRequest::DeleteNextWord),
             InputAction::DeleteLine => Ok(InputRequest::DeleteLine),
             InputAction::DeleteTillEnd => Ok(InputRequest::DeleteTillEnd),
+            InputAction::ToggleCase => Err(ConvertError::ConvertError),
+            InputAction::ToggleRegex => Err(ConvertError::ConvertError),
+            InputAction::Complete => Err(ConvertError::ConvertError),
+            InputAction::NextCompletion => Err(ConvertError::ConvertError),
+            InputAction::PrevCompletion => Err(ConvertError::ConvertError),
+            InputAction::HistoryPrev => Err(ConvertError::ConvertError),
+            InputAction::HistoryNext => Err(ConvertError::ConvertError),
+            InputAction::HistorySearch => Err(ConvertError::ConvertError),
             InputAction::Enter => Err(ConvertError::ConvertError),
             InputAction::Leave => Err(ConvertError::ConvertError),
         }
@@ -142,6 +375,12 @@ pub enum Mode {
     Zoom,
     Input,
     Visual,
+    Confirm,
+    Schema,
+    Blob,
+    Form,
+    Editor,
+    Picker,
 }
 
 impl Display for Mode {
@@ -151,6 +390,12 @@ impl Display for Mode {
             Mode::Zoom => write!(f, "Zoom"),
             Mode::Input => write!(f, "Input"),
             Mode::Visual => write!(f, "Visual"),
+            Mode::Confirm => write!(f, "Confirm"),
+            Mode::Schema => write!(f, "Schema"),
+            Mode::Blob => write!(f, "Blob"),
+            Mode::Form => write!(f, "Form"),
+            Mode::Editor => write!(f, "Editor"),
+            Mode::Picker => write!(f, "Picker"),
         }
     }
 }
@@ -165,11 +410,25 @@ pub struct KeyBind {
 #[serde(default)]
 pub struct Config {
     pub colors: Colors,
+    /// Command yanked text is also piped to, like "xclip -selection clipboard",
+    /// "wl-copy" or "pbcopy", for terminals without OSC 52
+    pub clipboard: Option<String>,
+    /// Queries to run by name from a list
+    pub queries: Vec<SavedQuery>,
+    /// The most queries and exec commands kept in the history of each database
+    pub history_size: usize,
     // pub keybinds: HashMap<KeyEvent, Action>,
     // pub keymodes: HashMap<Mode, Keymode>,
     pub main: HashMap<KeyEvent, MainAction>,
     pub zoom: HashMap<KeyEvent, ZoomAction>,
-    pub input: HashMap<KeyEvent, InputAction>
+    pub input: HashMap<KeyEvent, InputAction>,
+    pub confirm: HashMap<KeyEvent, ConfirmAction>,
+    pub schema: HashMap<KeyEvent, SchemaAction>,
+    pub blob: HashMap<KeyEvent, BlobAction>,
+    pub visual: HashMap<KeyEvent, VisualAction>,
+    pub form: HashMap<KeyEvent, FormAction>,
+    pub editor: HashMap<KeyEvent, EditorAction>,
+    pub picker: HashMap<KeyEvent, PickerAction>,
 }
 
 macro_rules! keypress {
@@ -198,8 +457,35 @@ impl Default for Config {
         main.insert(keypress!(KeyCode::Char('q'), KeyModifiers::NONE), MainAction::Quit);
         main.insert(keypress!(KeyCode::Char('r'), KeyModifiers::NONE), MainAction::Reload);
         main.insert(keypress!(KeyCode::Char('z'), KeyModifiers::NONE), MainAction::Zoom);
-        main.insert(keypress!(KeyCode::Char('i'), KeyModifiers::NONE), MainAction::InputCurrent("query".to_owned()));
-        main.insert(keypress!(KeyCode::Char('e'), KeyModifiers::NONE), MainAction::Input("exec ".to_owned()));
+        main.insert(keypress!(KeyCode::Char('i'), KeyModifiers::NONE), MainAction::Editor("query".to_owned()));
+        main.insert(keypress!(KeyCode::Char('e'), KeyModifiers::NONE), MainAction::Editor("exec".to_owned()));
+        main.insert(keypress!(KeyCode::Char(':'), KeyModifiers::NONE), MainAction::Input(String::new()));
+        main.insert(keypress!(KeyCode::Char('c'), KeyModifiers::NONE), MainAction::Edit);
+        main.insert(keypress!(KeyCode::Char('w'), KeyModifiers::NONE), MainAction::Commit);
+        main.insert(keypress!(KeyCode::Char('u'), KeyModifiers::NONE), MainAction::Rollback);
+        main.insert(keypress!(KeyCode::Char('/'), KeyModifiers::NONE), MainAction::Search);
+        main.insert(keypress!(KeyCode::Char('n'), KeyModifiers::NONE), MainAction::NextMatch);
+        main.insert(keypress!(KeyCode::Char('N'), KeyModifiers::NONE), MainAction::PrevMatch);
+        main.insert(keypress!(KeyCode::Char('S'), KeyModifiers::NONE), MainAction::Schema);
+        main.insert(keypress!(KeyCode::Char('>'), KeyModifiers::NONE), MainAction::Wider);
+        main.insert(keypress!(KeyCode::Char('<'), KeyModifiers::NONE), MainAction::Narrower);
+        main.insert(keypress!(KeyCode::Char('o'), KeyModifiers::NONE), MainAction::Sort);
+        main.insert(keypress!(KeyCode::Char('O'), KeyModifiers::NONE), MainAction::SortAdd);
+        main.insert(keypress!(KeyCode::Char('F'), KeyModifiers::NONE), MainAction::Filter);
+        main.insert(keypress!(KeyCode::Char('f'), KeyModifiers::NONE), MainAction::FilterValue);
+        main.insert(keypress!(KeyCode::Char('x'), KeyModifiers::NONE), MainAction::Exclude);
+        main.insert(keypress!(KeyCode::Backspace, KeyModifiers::NONE), MainAction::Unfilter);
+        main.insert(keypress!(KeyCode::Char('X'), KeyModifiers::NONE), MainAction::ClearFilters);
+        main.insert(keypress!(KeyCode::Char('y'), KeyModifiers::NONE), MainAction::Yank);
+        main.insert(keypress!(KeyCode::Char('Y'), KeyModifiers::NONE), MainAction::YankRow("tsv".to_owned()));
+        main.insert(keypress!(KeyCode::Char('y'), KeyModifiers::ALT), MainAction::YankRow("json".to_owned()));
+        main.insert(keypress!(KeyCode::Char('y'), KeyModifiers::CONTROL), MainAction::YankRow("sql".to_owned()));
+        main.insert(keypress!(KeyCode::Char('V'), KeyModifiers::NONE), MainAction::VisualLine);
+        main.insert(keypress!(KeyCode::Char('v'), KeyModifiers::NONE), MainAction::VisualBlock);
+        main.insert(keypress!(KeyCode::Char('a'), KeyModifiers::NONE), MainAction::Insert);
+        main.insert(keypress!(KeyCode::Char('A'), KeyModifiers::NONE), MainAction::Duplicate);
+        main.insert(keypress!(KeyCode::Char('d'), KeyModifiers::NONE), MainAction::Delete);
+        main.insert(keypress!(KeyCode::Char('Q'), KeyModifiers::NONE), MainAction::SavedQueries);
 
         let mut zoom = HashMap::new();
         zoom.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), ZoomAction::Back);
@@ -209,7 +495,86 @@ impl Default for Config {
         zoom.insert(keypress!(KeyCode::Left, KeyModifiers::NONE), ZoomAction::Prev);
         zoom.insert(keypress!(KeyCode::Char('h'), KeyModifiers::NONE), ZoomAction::Prev);
         zoom.insert(keypress!(KeyCode::Right, KeyModifiers::NONE), ZoomAction::Next);
-        zoom.insert(keypress!(KeyCode::Char('l'), KeyModifiers::NONE), ZoomAction::Prev);
+        zoom.insert(keypress!(KeyCode::Char('l'), KeyModifiers::NONE), ZoomAction::Next);
+        zoom.insert(keypress!(KeyCode::Char('b'), KeyModifiers::NONE), ZoomAction::Blob);
+
+        let mut visual = HashMap::new();
+        visual.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), VisualAction::Back);
+        visual.insert(keypress!(KeyCode::Char('q'), KeyModifiers::NONE), VisualAction::Back);
+        visual.insert(keypress!(KeyCode::Down, KeyModifiers::NONE), VisualAction::Next);
+        visual.insert(keypress!(KeyCode::Char('j'), KeyModifiers::NONE), VisualAction::Next);
+        visual.insert(keypress!(KeyCode::Up, KeyModifiers::NONE), VisualAction::Prev);
+        visual.insert(keypress!(KeyCode::Char('k'), KeyModifiers::NONE), VisualAction::Prev);
+        visual.insert(keypress!(KeyCode::Right, KeyModifiers::NONE), VisualAction::Hnext);
+        visual.insert(keypress!(KeyCode::Char('l'), KeyModifiers::NONE), VisualAction::Hnext);
+        visual.insert(keypress!(KeyCode::Left, KeyModifiers::NONE), VisualAction::Hprev);
+        visual.insert(keypress!(KeyCode::Char('h'), KeyModifiers::NONE), VisualAction::Hprev);
+        visual.insert(keypress!(KeyCode::Char('g'), KeyModifiers::NONE), VisualAction::First);
+        visual.insert(keypress!(KeyCode::Char('G'), KeyModifiers::NONE), VisualAction::Last);
+        visual.insert(keypress!(KeyCode::Char('V'), KeyModifiers::NONE), VisualAction::Line);
+        visual.insert(keypress!(KeyCode::Char('v'), KeyModifiers::NONE), VisualAction::Block);
+        visual.insert(keypress!(KeyCode::Char('y'), KeyModifiers::NONE), VisualAction::Yank("tsv".to_owned()));
+        visual.insert(keypress!(KeyCode::Char('y'), KeyModifiers::ALT), VisualAction::Yank("json".to_owned()));
+        visual.insert(keypress!(KeyCode::Char('y'), KeyModifiers::CONTROL), VisualAction::Yank("sql".to_owned()));
+        visual.insert(keypress!(KeyCode::Char(':'), KeyModifiers::NONE), VisualAction::Input(String::new()));
+        visual.insert(keypress!(KeyCode::Char('E'), KeyModifiers::NONE), VisualAction::Input("export ".to_owned()));
+        visual.insert(keypress!(KeyCode::Char('d'), KeyModifiers::NONE), VisualAction::Delete);
+        visual.insert(keypress!(KeyCode::Char('c'), KeyModifiers::NONE), VisualAction::Set);
+
+        let mut form = HashMap::new();
+        form.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), FormAction::Back);
+        form.insert(keypress!(KeyCode::Tab, KeyModifiers::NONE), FormAction::Next);
+        form.insert(keypress!(KeyCode::Down, KeyModifiers::NONE), FormAction::Next);
+        form.insert(keypress!(KeyCode::BackTab, KeyModifiers::SHIFT), FormAction::Prev);
+        form.insert(keypress!(KeyCode::Up, KeyModifiers::NONE), FormAction::Prev);
+        form.insert(keypress!(KeyCode::Enter, KeyModifiers::NONE), FormAction::Submit);
+
+        let mut editor = HashMap::new();
+        editor.insert(keypress!(KeyCode::Char('s'), KeyModifiers::CONTROL), EditorAction::Submit);
+        editor.insert(keypress!(KeyCode::Enter, KeyModifiers::ALT), EditorAction::Submit);
+        editor.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), EditorAction::Leave);
+        editor.insert(keypress!(KeyCode::Enter, KeyModifiers::NONE), EditorAction::Newline);
+        editor.insert(keypress!(KeyCode::Tab, KeyModifiers::NONE), EditorAction::Complete);
+        editor.insert(keypress!(KeyCode::Char('n'), KeyModifiers::CONTROL), EditorAction::NextCompletion);
+        editor.insert(keypress!(KeyCode::Char('p'), KeyModifiers::CONTROL), EditorAction::PrevCompletion);
+        editor.insert(keypress!(KeyCode::Char('t'), KeyModifiers::CONTROL), EditorAction::Indent);
+        editor.insert(keypress!(KeyCode::BackTab, KeyModifiers::SHIFT), EditorAction::Unindent);
+        editor.insert(keypress!(KeyCode::Left, KeyModifiers::NONE), EditorAction::Left);
+        editor.insert(keypress!(KeyCode::Right, KeyModifiers::NONE), EditorAction::Right);
+        editor.insert(keypress!(KeyCode::Up, KeyModifiers::NONE), EditorAction::Up);
+        editor.insert(keypress!(KeyCode::Down, KeyModifiers::NONE), EditorAction::Down);
+        editor.insert(keypress!(KeyCode::Home, KeyModifiers::NONE), EditorAction::Home);
+        editor.insert(keypress!(KeyCode::Char('a'), KeyModifiers::CONTROL), EditorAction::Home);
+        editor.insert(keypress!(KeyCode::End, KeyModifiers::NONE), EditorAction::End);
+        editor.insert(keypress!(KeyCode::Char('e'), KeyModifiers::CONTROL), EditorAction::End);
+        editor.insert(keypress!(KeyCode::Backspace, KeyModifiers::NONE), EditorAction::DeletePrevChar);
+        editor.insert(keypress!(KeyCode::Delete, KeyModifiers::NONE), EditorAction::DeleteNextChar);
+        editor.insert(keypress!(KeyCode::Char('r'), KeyModifiers::CONTROL), EditorAction::HistorySearch);
+
+        let mut picker = HashMap::new();
+        picker.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), PickerAction::Back);
+        picker.insert(keypress!(KeyCode::Down, KeyModifiers::NONE), PickerAction::Next);
+        picker.insert(keypress!(KeyCode::Char('n'), KeyModifiers::CONTROL), PickerAction::Next);
+        picker.insert(keypress!(KeyCode::Char('r'), KeyModifiers::CONTROL), PickerAction::Next);
+        picker.insert(keypress!(KeyCode::Up, KeyModifiers::NONE), PickerAction::Prev);
+        picker.insert(keypress!(KeyCode::Char('p'), KeyModifiers::CONTROL), PickerAction::Prev);
+        picker.insert(keypress!(KeyCode::Enter, KeyModifiers::NONE), PickerAction::Pick);
+
+        let mut blob = HashMap::new();
+        blob.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), BlobAction::Back);
+        blob.insert(keypress!(KeyCode::Char('q'), KeyModifiers::NONE), BlobAction::Back);
+        blob.insert(keypress!(KeyCode::Down, KeyModifiers::NONE), BlobAction::Down);
+        blob.insert(keypress!(KeyCode::Char('j'), KeyModifiers::NONE), BlobAction::Down);
+        blob.insert(keypress!(KeyCode::Up, KeyModifiers::NONE), BlobAction::Up);
+        blob.insert(keypress!(KeyCode::Char('k'), KeyModifiers::NONE), BlobAction::Up);
+        blob.insert(keypress!(KeyCode::PageDown, KeyModifiers::NONE), BlobAction::PageDown);
+        blob.insert(keypress!(KeyCode::Char('d'), KeyModifiers::CONTROL), BlobAction::PageDown);
+        blob.insert(keypress!(KeyCode::PageUp, KeyModifiers::NONE), BlobAction::PageUp);
+        blob.insert(keypress!(KeyCode::Char('u'), KeyModifiers::CONTROL), BlobAction::PageUp);
+        blob.insert(keypress!(KeyCode::Char('g'), KeyModifiers::NONE), BlobAction::First);
+        blob.insert(keypress!(KeyCode::Char('G'), KeyModifiers::NONE), BlobAction::Last);
+        blob.insert(keypress!(KeyCode::Char('s'), KeyModifiers::NONE), BlobAction::Save);
+        blob.insert(keypress!(KeyCode::Char('o'), KeyModifiers::NONE), BlobAction::Load);
 
         let mut input = HashMap::new();
         input.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), InputAction::Leave);
@@ -221,12 +586,51 @@ impl Default for Config {
         input.insert(keypress!(KeyCode::Char('a'), KeyModifiers::CONTROL), InputAction::GoToStart);
         input.insert(keypress!(KeyCode::Char('e'), KeyModifiers::CONTROL), InputAction::GoToEnd);
         input.insert(keypress!(KeyCode::Char('w'), KeyModifiers::CONTROL), InputAction::DeletePrevWord);
+        input.insert(keypress!(KeyCode::Char('c'), KeyModifiers::ALT), InputAction::ToggleCase);
+        input.insert(keypress!(KeyCode::Char('r'), KeyModifiers::ALT), InputAction::ToggleRegex);
+        input.insert(keypress!(KeyCode::Tab, KeyModifiers::NONE), InputAction::Complete);
+        input.insert(keypress!(KeyCode::Char('n'), KeyModifiers::CONTROL), InputAction::NextCompletion);
+        input.insert(keypress!(KeyCode::Char('p'), KeyModifiers::CONTROL), InputAction::PrevCompletion);
+        input.insert(keypress!(KeyCode::Up, KeyModifiers::NONE), InputAction::HistoryPrev);
+        input.insert(keypress!(KeyCode::Down, KeyModifiers::NONE), InputAction::HistoryNext);
+        input.insert(keypress!(KeyCode::Char('r'), KeyModifiers::CONTROL), InputAction::HistorySearch);
+
+        let mut confirm = HashMap::new();
+        confirm.insert(keypress!(KeyCode::Char('y'), KeyModifiers::NONE), ConfirmAction::Yes);
+        confirm.insert(keypress!(KeyCode::Enter, KeyModifiers::NONE), ConfirmAction::Yes);
+        confirm.insert(keypress!(KeyCode::Char('n'), KeyModifiers::NONE), ConfirmAction::No);
+        confirm.insert(keypress!(KeyCode::Char('q'), KeyModifiers::NONE), ConfirmAction::No);
+        confirm.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), ConfirmAction::No);
+
+        let mut schema = HashMap::new();
+        schema.insert(keypress!(KeyCode::Esc, KeyModifiers::NONE), SchemaAction::Back);
+        schema.insert(keypress!(KeyCode::Char('q'), KeyModifiers::NONE), SchemaAction::Back);
+        schema.insert(keypress!(KeyCode::Down, KeyModifiers::NONE), SchemaAction::Next);
+        schema.insert(keypress!(KeyCode::Char('j'), KeyModifiers::NONE), SchemaAction::Next);
+        schema.insert(keypress!(KeyCode::Up, KeyModifiers::NONE), SchemaAction::Prev);
+        schema.insert(keypress!(KeyCode::Char('k'), KeyModifiers::NONE), SchemaAction::Prev);
+        schema.insert(keypress!(KeyCode::Right, KeyModifiers::NONE), SchemaAction::Expand);
+        schema.insert(keypress!(KeyCode::Char('l'), KeyModifiers::NONE), SchemaAction::Expand);
+        schema.insert(keypress!(KeyCode::Left, KeyModifiers::NONE), SchemaAction::Collapse);
+        schema.insert(keypress!(KeyCode::Char('h'), KeyModifiers::NONE), SchemaAction::Collapse);
+        schema.insert(keypress!(KeyCode::Char(' '), KeyModifiers::NONE), SchemaAction::Toggle);
+        schema.insert(keypress!(KeyCode::Enter, KeyModifiers::NONE), SchemaAction::Open);
         
         Self { 
             colors: Colors::default(),
+            clipboard: None,
+            queries: Vec::new(),
+            history_size: 1000,
             main,
             zoom,
-            input
+            input,
+            confirm,
+            schema,
+            blob,
+            visual,
+            form,
+            editor,
+            picker,
         }
     }
 }
@@ -239,4 +643,12 @@ mod tests {
     fn test_serialize() {
         let _ = Config::default();
     }
+
+    #[test]
+    fn test_theme() {
+        let mut colors = Config::default().colors;
+        assert!(colors.theme().is_ok());
+        colors.theme = "nope".to_owned();
+        assert!(colors.theme().is_err());
+    }
 }
@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use rusqlite::{Connection, types::Value};
+
+use super::export::sql_literal;
+use super::schema::{foreign_keys, table_info};
+use super::sql::{quote, TableName};
+
+/// The rows to delete, by rowid or by primary key for WITHOUT ROWID tables
+#[derive(Clone, Debug)]
+pub enum Keys {
+    /// The name the rowid is selected by and the rowids
+    Rowids(&'static str, Vec<i64>),
+    /// The primary key columns and the key of every row
+    Primary(Vec<String>, Vec<Vec<Value>>),
+}
+
+impl Keys {
+    pub fn len(&self) -> usize {
+        match self {
+            Keys::Rowids(_, rowids) => rowids.len(),
+            Keys::Primary(_, keys) => keys.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A WHERE condition matching the rows
+    fn condition(&self) -> String {
+        match self {
+            Keys::Rowids(rowid, rowids) => {
+                let ids: Vec<String> = rowids.iter().map(|id| id.to_string()).collect();
+                format!("{} IN ({})", rowid, ids.join(", "))
+            }
+            Keys::Primary(columns, keys) => {
+                let columns: Vec<String> = columns.iter().map(|c| quote(c)).collect();
+                let keys: Vec<String> = keys.iter()
+                    .map(|key| {
+                        let values: Vec<String> = key.iter().map(sql_literal).collect();
+                        format!("({})", values.join(", "))
+                    })
+                    .collect();
+                format!("({}) IN (VALUES {})", columns.join(", "), keys.join(", "))
+            }
+        }
+    }
+}
+
+/// Rows in another table that reference the rows that are deleted
+pub struct Reference {
+    pub table: String,
+    pub columns: Vec<String>,
+    pub rows: usize,
+    pub on_delete: String,
+    /// Foreign keys are enforced, so on_delete happens
+    pub enforced: bool,
+}
+
+impl Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = if self.rows == 1 { "" } else { "s" };
+        write!(f, "{} row{} in {}({})", self.rows, s, self.table, self.columns.join(", "))?;
+        if !self.enforced {
+            return write!(f, " keep pointing at them, foreign keys are off so ON DELETE {} won't run", self.on_delete);
+        }
+        match self.on_delete.as_str() {
+            "CASCADE" => write!(f, " will be deleted too (ON DELETE CASCADE)"),
+            "SET NULL" => write!(f, " will be set to NULL (ON DELETE SET NULL)"),
+            "SET DEFAULT" => write!(f, " will be set to their default (ON DELETE SET DEFAULT)"),
+            action => write!(f, " point at them, the delete will fail (ON DELETE {})", action),
+        }
+    }
+}
+
+/// Find the rows in other tables of the same database that reference the rows of table
+pub fn references(conn: &Connection, table: &TableName, keys: &Keys) -> rusqlite::Result<Vec<Reference>> {
+    let enforced: bool = conn.query_row("PRAGMA foreign_keys", [], |r| r.get(0))?;
+    let master = match table.schema {
+        Some(ref schema) => format!("{}.sqlite_master", quote(schema)),
+        None => "sqlite_master".to_owned(),
+    };
+    let children: Vec<String> = {
+        let mut stmt = conn.prepare(&format!("SELECT name FROM {} WHERE type = 'table'", master))?;
+        let rows = stmt.query_map([], |r| r.get(0))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let mut references = Vec::new();
+    for child in children {
+        let child = table.sibling(&child);
+        let fks = foreign_keys(conn, &child)?;
+        let mut ids: Vec<i64> = fks.iter().map(|fk| fk.id).collect();
+        ids.dedup();
+        for id in ids {
+            // the columns of a foreign key are in order, composite keys have several
+            let fk: Vec<_> = fks.iter().filter(|fk| fk.id == id).collect();
+            if !fk[0].table.eq_ignore_ascii_case(&table.name) {
+                continue;
+            }
+            let from: Vec<String> = fk.iter().map(|fk| quote(&fk.from)).collect();
+            let to: Vec<String> = match fk[0].to {
+                Some(_) => fk.iter().filter_map(|fk| fk.to.as_deref()).map(quote).collect(),
+                // without columns the key references the primary key
+                None => {
+                    let mut pk: Vec<_> = table_info(conn, table)?.into_iter().filter(|c| c.pk > 0).collect();
+                    pk.sort_by_key(|c| c.pk);
+                    pk.iter().map(|c| quote(&c.name)).collect()
+                }
+            };
+            let sql = format!(
+                "SELECT COUNT(*) FROM {} WHERE ({}) IN (SELECT {} FROM {} WHERE {})",
+                child.sql(), from.join(", "), to.join(", "), table.sql(), keys.condition()
+            );
+            let rows: i64 = conn.query_row(&sql, [], |r| r.get(0))?;
+            if rows > 0 {
+                references.push(Reference {
+                    table: child.to_string(),
+                    columns: fk.iter().map(|fk| fk.from.clone()).collect(),
+                    rows: rows as usize,
+                    on_delete: fk[0].on_delete.clone(),
+                    enforced,
+                });
+            }
+        }
+    }
+    Ok(references)
+}
+
+/// Delete the rows from table, returning how many were deleted
+pub fn delete(conn: &Connection, table: &TableName, keys: &Keys) -> rusqlite::Result<usize> {
+    conn.execute(&format!("DELETE FROM {} WHERE {}", table.sql(), keys.condition()), [])
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::{Connection, types::Value};
+
+    use super::{delete, references, Keys};
+    use crate::schema::rowid_alias;
+    use crate::sql::TableName;
+
+    #[test]
+    fn test_references() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;
+            CREATE TABLE p(a INT, b INT, PRIMARY KEY (a, b)) WITHOUT ROWID;
+            CREATE TABLE c(x INT, y INT, FOREIGN KEY (x, y) REFERENCES p ON DELETE CASCADE);
+            CREATE TABLE d(x INT, FOREIGN KEY (x) REFERENCES q(id));
+            INSERT INTO p VALUES (1, 1), (1, 2);
+            INSERT INTO c VALUES (1, 1), (1, 1), (1, 2);").unwrap();
+        let p = TableName::new(None, "p");
+        let keys = Keys::Primary(vec!["a".to_owned(), "b".to_owned()], vec![vec![Value::Integer(1), Value::Integer(1)]]);
+        let refs = references(&conn, &p, &keys).unwrap();
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].to_string(), "2 rows in c(x, y) will be deleted too (ON DELETE CASCADE)");
+        assert_eq!(delete(&conn, &p, &keys).unwrap(), 1);
+        let left: i64 = conn.query_row("SELECT COUNT(*) FROM c", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, 1);
+    }
+
+    #[test]
+    fn test_rowid_column() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t(rowid TEXT, v); INSERT INTO t VALUES ('abc', 1), (NULL, 2);").unwrap();
+        let t = TableName::new(None, "t");
+        let alias = rowid_alias(&conn, &t).unwrap().unwrap();
+        assert_eq!(alias, "_rowid_");
+        assert_eq!(delete(&conn, &t, &Keys::Rowids(alias, vec![2])).unwrap(), 1);
+        let left: String = conn.query_row("SELECT rowid FROM t", [], |r| r.get(0)).unwrap();
+        assert_eq!(left, "abc");
+    }
+}
@@ -0,0 +1,417 @@
+use std::convert::TryFrom;
+
+use ratatui::{prelude::Backend, Frame, text::{Line, Span}, widgets::{Block, Borders, Clear, Paragraph}};
+
+use super::complete::{Completion, Completions};
+use super::history::{History, Recall, Recalled};
+use super::input::InputType;
+use super::popup::centered_rect;
+use super::sql::is_keyword;
+use super::theme::Theme;
+
+/// Spaces added by tab and after an open parenthesis
+const INDENT: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Token {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    /// A parenthesis outside of strings and comments
+    Bracket,
+    Other,
+}
+
+/// Split a line of sql into tokens, as (kind, first char, char after the last),
+/// strings and comments end with the line
+pub fn tokens(line: &str) -> Vec<(Token, usize, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let kind = if c == '-' && chars.get(i + 1) == Some(&'-') {
+            i = chars.len();
+            Token::Comment
+        } else if c == '\'' || c == '"' || c == '`' {
+            // quoted identifiers are skipped so their words aren't keywords
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            if c == '\'' { Token::String } else { Token::Other }
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            Token::Number
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if is_keyword(&word) { Token::Keyword } else { Token::Other }
+        } else if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            Token::Other
+        } else {
+            i += 1;
+            match c {
+                '(' | ')' => Token::Bracket,
+                _ => Token::Other,
+            }
+        };
+        tokens.push((kind, start, i));
+    }
+    tokens
+}
+
+/// The byte index of the char at col, or the length if it's past the end
+fn byte(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
+
+fn width(line: &str) -> usize {
+    line.chars().count()
+}
+
+fn indentation(line: &str) -> usize {
+    line.chars().take_while(|c| *c == ' ').count()
+}
+
+/// The bracket at index at and the one that closes or opens it, as (line, char, bracket)
+fn pair(brackets: &[(usize, usize, char)], at: usize) -> Option<usize> {
+    let mut depth = 0;
+    let step = |b: &(usize, usize, char), open: char| if b.2 == open { 1 } else { -1 };
+    if brackets[at].2 == '(' {
+        for (i, b) in brackets.iter().enumerate().skip(at) {
+            depth += step(b, '(');
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    } else {
+        for (i, b) in brackets[..=at].iter().enumerate().rev() {
+            depth += step(b, ')');
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+/// A popup to write sql that spans several lines
+pub struct Editor {
+    /// Query or Exec, what happens with the sql
+    pub kind: InputType,
+    lines: Vec<String>,
+    /// The cursor as (line, char)
+    cursor: (usize, usize),
+    /// The first line and char shown
+    offset: (usize, usize),
+    /// Names the word before the cursor can be completed to
+    pub completion: Option<Completion>,
+    /// Where going up from the first line is in the history, and the kind and text before
+    pub recall: Recall<(String, String)>,
+}
+
+impl Editor {
+    /// Start with text, the cursor at its end
+    pub fn new(kind: InputType, text: &str) -> Self {
+        let mut lines: Vec<String> = text.lines().map(|l| l.to_owned()).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        let cursor = (lines.len() - 1, width(&lines[lines.len() - 1]));
+        Editor {
+            kind,
+            lines,
+            cursor,
+            offset: (0, 0),
+            completion: None,
+            recall: Recall::default(),
+        }
+    }
+
+    /// Replace the sql, the kind is query or exec
+    pub fn set(&mut self, kind: &str, text: &str) {
+        let recall = std::mem::take(&mut self.recall);
+        let kind = InputType::try_from(kind).unwrap_or(InputType::Exec);
+        *self = Editor { recall, ..Editor::new(kind, text) };
+    }
+
+    /// Show the sql run before this one
+    pub fn history_prev(&mut self, history: &History) {
+        let draft = (self.kind.to_string(), self.text());
+        if let Some(entry) = self.recall.prev(history, || draft) {
+            self.set(&entry.kind, &entry.sql);
+        }
+    }
+
+    /// Show the sql run after this one, or what was typed after the newest
+    pub fn history_next(&mut self, history: &History) {
+        match self.recall.next(history) {
+            Some(Recalled::Entry(entry)) => self.set(&entry.kind, &entry.sql),
+            Some(Recalled::Draft((kind, text))) => self.set(&kind, &text),
+            None => {}
+        }
+    }
+
+    /// The cursor is on the last line
+    pub fn on_last_line(&self) -> bool {
+        self.cursor.0 + 1 == self.lines.len()
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let (row, col) = self.cursor;
+        let blank = self.lines[row].chars().take(col).all(|c| c == ' ');
+        let i = byte(&self.lines[row], col);
+        self.lines[row].insert(i, c);
+        self.cursor.1 += 1;
+        // a parenthesis that starts a line lines up with the line it closes
+        if c == ')' && blank {
+            let brackets = self.brackets();
+            let open = brackets.iter().position(|b| (b.0, b.1) == (row, col))
+                .and_then(|at| pair(&brackets, at))
+                .map(|i| brackets[i].0);
+            if let Some(open) = open {
+                let indent = indentation(&self.lines[open]);
+                self.lines[row] = format!("{}{}", " ".repeat(indent), self.lines[row].trim_start());
+                self.cursor.1 = indent + 1;
+            }
+        }
+    }
+
+    /// Break the line at the cursor, the new line is indented like the one before
+    /// and a level more after an open parenthesis
+    pub fn newline(&mut self) {
+        let (row, col) = self.cursor;
+        let i = byte(&self.lines[row], col);
+        let rest = self.lines[row].split_off(i);
+        let before = &self.lines[row];
+        let mut indent = indentation(before);
+        if before.trim_end().ends_with('(') {
+            indent += INDENT;
+        }
+        self.lines.insert(row + 1, format!("{}{}", " ".repeat(indent), rest.trim_start()));
+        self.cursor = (row + 1, indent);
+    }
+
+    /// Delete the char before the cursor, a whole level in the indentation
+    pub fn backspace(&mut self) {
+        let (row, col) = self.cursor;
+        if col > 0 {
+            let line = &mut self.lines[row];
+            let n = match line.chars().take(col).all(|c| c == ' ') {
+                true => (col - 1) % INDENT + 1,
+                false => 1,
+            };
+            let range = byte(line, col - n)..byte(line, col);
+            line.replace_range(range, "");
+            self.cursor.1 -= n;
+        } else if row > 0 {
+            let line = self.lines.remove(row);
+            self.cursor = (row - 1, width(&self.lines[row - 1]));
+            self.lines[row - 1].push_str(&line);
+        }
+    }
+
+    /// Delete the char under the cursor, at the end of a line join the next one
+    pub fn delete(&mut self) {
+        let (row, col) = self.cursor;
+        if col < width(&self.lines[row]) {
+            let i = byte(&self.lines[row], col);
+            self.lines[row].remove(i);
+        } else if row + 1 < self.lines.len() {
+            let next = self.lines.remove(row + 1);
+            self.lines[row].push_str(&next);
+        }
+    }
+
+    /// Add spaces up to the next level of indentation
+    pub fn indent(&mut self) {
+        let n = INDENT - self.cursor.1 % INDENT;
+        for _ in 0..n {
+            self.insert(' ');
+        }
+    }
+
+    /// Remove a level of indentation from the line
+    pub fn unindent(&mut self) {
+        let line = &mut self.lines[self.cursor.0];
+        let n = indentation(line).min(INDENT);
+        line.replace_range(..n, "");
+        self.cursor.1 = self.cursor.1.saturating_sub(n);
+    }
+
+    pub fn left(&mut self) {
+        let (row, col) = self.cursor;
+        if col > 0 {
+            self.cursor.1 -= 1;
+        } else if row > 0 {
+            self.cursor = (row - 1, width(&self.lines[row - 1]));
+        }
+    }
+
+    pub fn right(&mut self) {
+        let (row, col) = self.cursor;
+        if col < width(&self.lines[row]) {
+            self.cursor.1 += 1;
+        } else if row + 1 < self.lines.len() {
+            self.cursor = (row + 1, 0);
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.cursor.0 > 0 {
+            self.cursor.0 -= 1;
+            self.cursor.1 = self.cursor.1.min(width(&self.lines[self.cursor.0]));
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.cursor.0 + 1 < self.lines.len() {
+            self.cursor.0 += 1;
+            self.cursor.1 = self.cursor.1.min(width(&self.lines[self.cursor.0]));
+        }
+    }
+
+    /// Go to the first char after the indentation, or the start of the line if already there
+    pub fn home(&mut self) {
+        let indent = indentation(&self.lines[self.cursor.0]);
+        self.cursor.1 = if self.cursor.1 == indent { 0 } else { indent };
+    }
+
+    pub fn end(&mut self) {
+        self.cursor.1 = width(&self.lines[self.cursor.0]);
+    }
+
+    /// Look for completions of the word before the cursor
+    pub fn update_completion(&mut self, completions: &Completions) {
+        let (row, col) = self.cursor;
+        let mut before: String = self.lines[..row].iter().map(|l| format!("{}\n", l)).collect();
+        before.push_str(&self.lines[row][..byte(&self.lines[row], col)]);
+        self.completion = Completion::new(completions, &self.text(), &before);
+    }
+
+    /// Replace the word before the cursor with the selected completion
+    pub fn complete(&mut self) {
+        if let Some(completion) = self.completion.take() {
+            let (row, col) = self.cursor;
+            let start = col - completion.word.chars().count();
+            let line = &mut self.lines[row];
+            let range = byte(line, start)..byte(line, col);
+            line.replace_range(range, completion.selected());
+            self.cursor.1 = start + width(completion.selected());
+        }
+    }
+
+    /// Every parenthesis as (line, char, parenthesis)
+    fn brackets(&self) -> Vec<(usize, usize, char)> {
+        self.lines.iter().enumerate()
+            .flat_map(|(row, line)| {
+                let chars: Vec<char> = line.chars().collect();
+                tokens(line).into_iter()
+                    .filter(|t| t.0 == Token::Bracket)
+                    .map(move |t| (row, t.1, chars[t.1]))
+            })
+            .collect()
+    }
+
+    /// The parenthesis under or before the cursor and the one that matches it
+    pub fn matching(&self) -> Option<((usize, usize), (usize, usize))> {
+        let brackets = self.brackets();
+        let (row, col) = self.cursor;
+        let at = brackets.iter().position(|b| (b.0, b.1) == (row, col))
+            .or_else(|| brackets.iter().position(|b| col > 0 && (b.0, b.1) == (row, col - 1)))?;
+        let other = pair(&brackets, at)?;
+        Some(((brackets[at].0, brackets[at].1), (brackets[other].0, brackets[other].1)))
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, theme: &Theme) {
+        let area = centered_rect(80, 80, f.size());
+        let height = (area.height.saturating_sub(2) as usize).max(1);
+        let inner = (area.width.saturating_sub(2) as usize).max(1);
+        // scroll just enough to keep the cursor inside
+        let (row, col) = self.cursor;
+        self.offset.0 = self.offset.0.min(row).max((row + 1).saturating_sub(height));
+        self.offset.1 = self.offset.1.min(col).max((col + 1).saturating_sub(inner));
+
+        let matching = self.matching();
+        let lines: Vec<Line> = self.lines.iter().enumerate().skip(self.offset.0).take(height)
+            .map(|(r, line)| {
+                let chars: Vec<char> = line.chars().collect();
+                let spans: Vec<Span> = tokens(line).into_iter()
+                    .map(|(kind, start, end)| {
+                        let style = match kind {
+                            Token::Keyword => theme.keyword,
+                            Token::String => theme.string,
+                            Token::Number => theme.number,
+                            Token::Comment => theme.comment,
+                            Token::Bracket if matching.is_some_and(|(a, b)| a == (r, start) || b == (r, start)) => theme.matched,
+                            Token::Bracket | Token::Other => theme.input,
+                        };
+                        Span::styled(chars[start..end].iter().collect::<String>(), style)
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect();
+        let block = Block::default()
+            .title(self.kind.to_string())
+            .title_style(theme.header)
+            .borders(Borders::ALL);
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .scroll((0, self.offset.1 as u16));
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+        let cursor = (area.x + 1 + (col - self.offset.1) as u16, area.y + 1 + (row - self.offset.0) as u16);
+        if let Some(ref completion) = self.completion {
+            completion.render(f, cursor, theme);
+        }
+        f.set_cursor(cursor.0, cursor.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokens, Editor, Token};
+    use crate::input::InputType;
+
+    #[test]
+    fn test_editor() {
+        let kinds: Vec<Token> = tokens("select 'a(' -- x").into_iter().map(|t| t.0).collect();
+        assert_eq!(kinds, [Token::Keyword, Token::Other, Token::String, Token::Other, Token::Comment]);
+
+        let mut editor = Editor::new(InputType::Query, "SELECT * FROM t WHERE id IN (");
+        editor.newline();
+        assert_eq!(editor.cursor(), (1, 4));
+        "SELECT id FROM u".chars().for_each(|c| editor.insert(c));
+        editor.newline();
+        editor.insert(')');
+        assert_eq!(editor.text(), "SELECT * FROM t WHERE id IN (\n    SELECT id FROM u\n)");
+        assert_eq!(editor.matching(), Some(((2, 0), (0, 28))));
+
+        editor.up();
+        editor.home();
+        editor.backspace();
+        assert_eq!(editor.cursor(), (1, 0));
+        editor.backspace();
+        assert_eq!(editor.text(), "SELECT * FROM t WHERE id IN (SELECT id FROM u\n)");
+    }
+}
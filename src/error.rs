@@ -1,5 +1,9 @@
+use std::sync::Arc;
+
 use thiserror::Error;
 
+use crate::export::Format;
+
 #[derive(Error, Debug)]
 pub enum ConvertError {
     #[error("Convertion error: report upstream")]
@@ -7,4 +11,89 @@ pub enum ConvertError {
 
     #[error("Not a valid command: {0}")]
     InputTypeError(String),
+
+    #[error("Not a valid theme: {0}, use default, light or mono")]
+    ThemeError(String),
+
+    #[error("Not a valid format: {0}, use csv, tsv, json, ndjson, sql or table")]
+    FormatError(String),
+}
+
+#[derive(Error, Debug)]
+pub enum EditError {
+    #[error("Nothing selected")]
+    NoSelection,
+
+    #[error("Can't edit rows without a rowid")]
+    NoRowid,
+
+    #[error("Can't edit blobs")]
+    Blob,
+
+    #[error("Views are read-only")]
+    ReadOnly,
+
+    #[error("Rows can only be inserted into or deleted from tables")]
+    NotTable,
+}
+
+#[derive(Error, Debug)]
+pub enum GrepError {
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
+
+    #[error(transparent)]
+    Sql(#[from] rusqlite::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum CountError {
+    /// Counting failed before, it isn't tried again
+    #[error("Can't count the rows: {0}")]
+    Failed(Arc<rusqlite::Error>),
+
+    #[error(transparent)]
+    Sql(#[from] rusqlite::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+
+    #[error(transparent)]
+    Sql(#[from] rusqlite::Error),
+
+    #[error("Can't import {0:?}, use csv, tsv or ndjson")]
+    Format(Format),
+
+    #[error("The file has no columns")]
+    NoColumns,
+
+    #[error("No column {0} in {1}")]
+    Column(String, String),
+}
+
+#[derive(Error, Debug)]
+pub enum FormError {
+    #[error("{0}: {1}")]
+    Value(String, String),
+
+    #[error(transparent)]
+    Sql(#[from] rusqlite::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum FilterError {
+    #[error("No column {0}")]
+    Column(String),
+
+    #[error("Not an operator: {0}, use =, !=, <, <=, >, >=, like, in, is null or is not null")]
+    Operator(String),
+
+    #[error("Missing a value after {0}")]
+    Value(String),
 }
@@ -0,0 +1,257 @@
+use std::{io::{self, Write}, str::FromStr, convert::TryFrom};
+
+use base64::Engine;
+use rusqlite::types::Value;
+
+use crate::error::ConvertError;
+use crate::format;
+use crate::sql::quote;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    Sql,
+    /// Aligned columns for reading in a terminal
+    Table,
+}
+
+impl TryFrom<&str> for Format {
+    type Error = ConvertError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "sql" => Ok(Format::Sql),
+            "table" => Ok(Format::Table),
+            _ => Err(ConvertError::FormatError(value.to_owned())),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = ConvertError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::try_from(s)
+    }
+}
+
+pub fn hex(b: &[u8]) -> String {
+    b.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn base64(b: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(b)
+}
+
+/// A field in a csv file, quoted if needed
+fn csv_field(v: &Value) -> String {
+    let text = match v {
+        Value::Null => return String::new(),
+        Value::Blob(b) => return hex(b),
+        Value::Integer(i) => return i.to_string(),
+        Value::Real(f) => return f.to_string(),
+        Value::Text(t) => t,
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+/// A field in a tsv file, tabs and newlines are escaped and NULL is \N
+fn tsv_field(v: &Value) -> String {
+    match v {
+        Value::Null => "\\N".to_owned(),
+        Value::Blob(b) => hex(b),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(t) => t
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+    }
+}
+
+fn json_value(v: &Value) -> String {
+    match v {
+        Value::Null => "null".to_owned(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => serde_json::to_string(f).unwrap_or_else(|_| "null".to_owned()),
+        Value::Text(t) => serde_json::to_string(t).unwrap_or_default(),
+        Value::Blob(b) => format!("\"{}\"", base64(b)),
+    }
+}
+
+/// A value as a sql literal
+pub fn sql_literal(v: &Value) -> String {
+    match v {
+        Value::Null => "NULL".to_owned(),
+        Value::Integer(i) => i.to_string(),
+        // sqlite stores NaN as NULL and reads a number too large for a double as infinity
+        Value::Real(f) if f.is_nan() => "NULL".to_owned(),
+        Value::Real(f) if f.is_infinite() => if *f > 0.0 { "9e999" } else { "-9e999" }.to_owned(),
+        Value::Real(f) => format!("{:?}", f),
+        Value::Text(t) => format!("'{}'", t.replace('\'', "''")),
+        Value::Blob(b) => format!("X'{}'", hex(b)),
+    }
+}
+
+/// Writes rows in a format, one row at a time
+pub struct Exporter<W: Write> {
+    w: W,
+    format: Format,
+    table: String,
+    columns: Vec<String>,
+    rows: usize,
+    /// Rows of a table are kept until the width of the columns is known,
+    /// numbers are aligned to the right
+    cells: Vec<Vec<(String, bool)>>,
+}
+
+impl<W: Write> Exporter<W> {
+    /// Start exporting, table is only used for sql INSERT statements
+    pub fn new(mut w: W, format: Format, table: &str, columns: &[String]) -> io::Result<Self> {
+        match format {
+            Format::Csv => {
+                let header: Vec<String> = columns.iter().map(|c| csv_field(&Value::Text(c.clone()))).collect();
+                writeln!(w, "{}", header.join(","))?;
+            }
+            Format::Tsv => {
+                let header: Vec<String> = columns.iter().map(|c| tsv_field(&Value::Text(c.clone()))).collect();
+                writeln!(w, "{}", header.join("\t"))?;
+            }
+            Format::Json => write!(w, "[")?,
+            Format::Ndjson | Format::Sql | Format::Table => {}
+        }
+        Ok(Exporter {
+            w,
+            format,
+            table: quote(table),
+            columns: columns.to_vec(),
+            rows: 0,
+            cells: Vec::new(),
+        })
+    }
+
+    fn object(&self, row: &[Value]) -> String {
+        let fields: Vec<String> = self.columns.iter().zip(row.iter())
+            .map(|(c, v)| format!("{}:{}", json_value(&Value::Text(c.clone())), json_value(v)))
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+
+    pub fn row(&mut self, row: &[Value]) -> io::Result<()> {
+        match self.format {
+            Format::Csv => {
+                let fields: Vec<String> = row.iter().map(csv_field).collect();
+                writeln!(self.w, "{}", fields.join(","))?;
+            }
+            Format::Tsv => {
+                let fields: Vec<String> = row.iter().map(tsv_field).collect();
+                writeln!(self.w, "{}", fields.join("\t"))?;
+            }
+            Format::Json => {
+                let sep = if self.rows == 0 { "\n" } else { ",\n" };
+                let obj = self.object(row);
+                write!(self.w, "{}  {}", sep, obj)?;
+            }
+            Format::Ndjson => {
+                let obj = self.object(row);
+                writeln!(self.w, "{}", obj)?;
+            }
+            Format::Sql => {
+                let columns: Vec<String> = self.columns.iter().map(|c| quote(c)).collect();
+                let values: Vec<String> = row.iter().map(sql_literal).collect();
+                writeln!(self.w, "INSERT INTO {} ({}) VALUES ({});", self.table, columns.join(", "), values.join(", "))?;
+            }
+            Format::Table => {
+                self.cells.push(row.iter().map(|v| (format::show(v).replace('\n', " "), format::is_numeric(v))).collect());
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn table(&mut self) -> io::Result<()> {
+        let mut widths: Vec<usize> = self.columns.iter().map(|c| c.chars().count()).collect();
+        for row in self.cells.iter() {
+            for (w, cell) in widths.iter_mut().zip(row.iter()) {
+                *w = (*w).max(cell.0.chars().count());
+            }
+        }
+        let line = |cells: &[(String, bool)]| {
+            let cells: Vec<String> = cells.iter().zip(widths.iter())
+                .map(|((c, numeric), w)| match numeric {
+                    true => format::align_right(c, *w),
+                    false => format!("{:w$}", c, w = w),
+                })
+                .collect();
+            cells.join("  ").trim_end().to_owned()
+        };
+        let header: Vec<(String, bool)> = self.columns.iter().map(|c| (c.clone(), false)).collect();
+        writeln!(self.w, "{}", line(&header))?;
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        writeln!(self.w, "{}", rule.join("  "))?;
+        for row in self.cells.iter() {
+            writeln!(self.w, "{}", line(row))?;
+        }
+        Ok(())
+    }
+
+    /// Finish the export, returning the number of rows written
+    pub fn finish(mut self) -> io::Result<usize> {
+        match self.format {
+            Format::Json => writeln!(self.w, "\n]")?,
+            Format::Table => self.table()?,
+            _ => {}
+        }
+        self.w.flush()?;
+        Ok(self.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::{Connection, types::Value};
+
+    use super::{sql_literal, Exporter, Format};
+
+    fn export(format: Format) -> String {
+        let columns = vec!["id".to_owned(), "name".to_owned(), "data".to_owned()];
+        let mut out = Vec::new();
+        let mut exporter = Exporter::new(&mut out, format, "t", &columns).unwrap();
+        exporter.row(&[Value::Integer(1), Value::Text("a,\"b\"".to_owned()), Value::Blob(vec![0xde, 0xad])]).unwrap();
+        exporter.row(&[Value::Real(1.5), Value::Null, Value::Null]).unwrap();
+        exporter.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(export(Format::Csv), "id,name,data\n1,\"a,\"\"b\"\"\",dead\n1.5,,\n");
+        assert_eq!(export(Format::Tsv), "id\tname\tdata\n1\ta,\"b\"\tdead\n1.5\t\\N\t\\N\n");
+        assert_eq!(export(Format::Ndjson), "{\"id\":1,\"name\":\"a,\\\"b\\\"\",\"data\":\"3q0=\"}\n{\"id\":1.5,\"name\":null,\"data\":null}\n");
+        assert_eq!(export(Format::Sql), "INSERT INTO \"t\" (\"id\", \"name\", \"data\") VALUES (1, 'a,\"b\"', X'dead');\nINSERT INTO \"t\" (\"id\", \"name\", \"data\") VALUES (1.5, NULL, NULL);\n");
+        assert!(export(Format::Json).starts_with("[\n  {\"id\":1,"));
+        assert_eq!(export(Format::Table), "id   name   data\n---  -----  ----------\n  1  a,\"b\"  <2 B> dead\n1.5  NULL   NULL\n");
+    }
+
+    #[test]
+    fn test_sql_literal() {
+        let conn = Connection::open_in_memory().unwrap();
+        for f in [f64::INFINITY, f64::NEG_INFINITY, 0.1] {
+            let back: f64 = conn.query_row(&format!("SELECT {}", sql_literal(&Value::Real(f))), [], |r| r.get(0)).unwrap();
+            assert_eq!(back, f);
+        }
+        assert_eq!(sql_literal(&Value::Real(f64::NAN)), "NULL");
+    }
+}
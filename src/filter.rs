@@ -0,0 +1,248 @@
+use std::fmt::Display;
+
+use rusqlite::types::Value;
+
+use super::error::FilterError;
+use super::export::sql_literal;
+use super::format;
+use super::sql::{quote, skip_quoted};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Op {
+    Eq,
+    /// Not equal, NULL counts as a value so rows with NULL are kept
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    IsNull,
+    NotNull,
+    In,
+}
+
+/// Operators in the order they are tried when parsing, longer ones first
+const OPS: [(&str, Op); 11] = [
+    ("is not null", Op::NotNull),
+    ("is null", Op::IsNull),
+    ("like", Op::Like),
+    ("!=", Op::Ne),
+    ("<>", Op::Ne),
+    ("in", Op::In),
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("=", Op::Eq),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+];
+
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Op::Eq => write!(f, "="),
+            Op::Ne => write!(f, "!="),
+            Op::Lt => write!(f, "<"),
+            Op::Le => write!(f, "<="),
+            Op::Gt => write!(f, ">"),
+            Op::Ge => write!(f, ">="),
+            Op::Like => write!(f, "like"),
+            Op::IsNull => write!(f, "is null"),
+            Op::NotNull => write!(f, "is not null"),
+            Op::In => write!(f, "in"),
+        }
+    }
+}
+
+/// A condition on a column, values is empty for IS NULL and has one value
+/// for everything but IN
+#[derive(Clone, PartialEq, Debug)]
+pub struct Condition {
+    pub column: String,
+    pub op: Op,
+    pub values: Vec<Value>,
+}
+
+/// Read a value typed in the filter, numbers are numbers unless they are quoted
+pub fn parse_value(text: &str) -> Value {
+    let text = text.trim();
+    for quote in ['\'', '"'] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return Value::Text(text[1..text.len() - 1].to_owned());
+        }
+    }
+    if let Ok(i) = text.parse() {
+        return Value::Integer(i);
+    }
+    if let Ok(f) = text.parse() {
+        return Value::Real(f);
+    }
+    Value::Text(text.to_owned())
+}
+
+/// Split the values of an IN list on the commas that aren't quoted
+fn split_values(text: &str) -> Vec<&str> {
+    let mut values = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' | '"' => skip_quoted(&mut chars, c),
+            ',' => {
+                values.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(&text[start..]);
+    values
+}
+
+impl Condition {
+    /// Keep the rows where column has value
+    pub fn is(column: &str, value: &Value) -> Self {
+        let (op, values) = match value {
+            Value::Null => (Op::IsNull, vec![]),
+            v => (Op::Eq, vec![v.clone()]),
+        };
+        Condition { column: column.to_owned(), op, values }
+    }
+
+    /// Keep the rows where column doesn't have value
+    pub fn is_not(column: &str, value: &Value) -> Self {
+        let (op, values) = match value {
+            Value::Null => (Op::NotNull, vec![]),
+            v => (Op::Ne, vec![v.clone()]),
+        };
+        Condition { column: column.to_owned(), op, values }
+    }
+
+    /// Parse "<column> <op> [value]", column is one of columns
+    pub fn parse(text: &str, columns: &[String]) -> Result<Self, FilterError> {
+        let text = text.trim();
+        // the longest name that matches a whole word, names can have spaces
+        let column = columns.iter()
+            .filter(|c| match text.get(..c.len()) {
+                Some(start) => start.eq_ignore_ascii_case(c)
+                    && !text[c.len()..].starts_with(|ch: char| ch.is_alphanumeric() || ch == '_'),
+                None => false,
+            })
+            .max_by_key(|c| c.len())
+            .ok_or_else(|| FilterError::Column(text.split_whitespace().next().unwrap_or_default().to_owned()))?;
+        let rest = text[column.len()..].trim_start();
+        let lower = rest.to_lowercase();
+        let (name, op) = OPS.iter()
+            .find(|(name, _)| lower.starts_with(name))
+            .ok_or_else(|| FilterError::Operator(rest.to_owned()))?;
+        let value = rest[name.len()..].trim();
+        let values = match op {
+            Op::IsNull | Op::NotNull if value.is_empty() => vec![],
+            Op::IsNull | Op::NotNull => return Err(FilterError::Operator(rest.to_owned())),
+            _ if value.is_empty() => return Err(FilterError::Value(op.to_string())),
+            Op::In => split_values(value.trim_start_matches('(').trim_end_matches(')')).into_iter().map(parse_value).collect(),
+            _ => vec![parse_value(value)],
+        };
+        Ok(Condition { column: column.clone(), op: *op, values })
+    }
+
+    /// The condition as sql
+    pub fn sql(&self) -> String {
+        let column = quote(&self.column);
+        let value = self.values.first().map(sql_literal).unwrap_or_default();
+        match self.op {
+            Op::Eq => format!("{} = {}", column, value),
+            Op::Ne => format!("{} IS NOT {}", column, value),
+            Op::Lt => format!("{} < {}", column, value),
+            Op::Le => format!("{} <= {}", column, value),
+            Op::Gt => format!("{} > {}", column, value),
+            Op::Ge => format!("{} >= {}", column, value),
+            Op::Like => format!("{} LIKE {}", column, value),
+            Op::IsNull => format!("{} IS NULL", column),
+            Op::NotNull => format!("{} IS NOT NULL", column),
+            Op::In => {
+                let values: Vec<String> = self.values.iter().map(sql_literal).collect();
+                format!("{} IN ({})", column, values.join(", "))
+            }
+        }
+    }
+
+    /// The short text shown in the chip of the condition
+    pub fn chip(&self) -> String {
+        let values: Vec<String> = self.values.iter().map(format::show).collect();
+        match self.op {
+            Op::IsNull | Op::NotNull => format!("{} {}", self.column, self.op),
+            Op::In => format!("{} in ({})", self.column, values.join(", ")),
+            op => format!("{} {} {}", self.column, op, values.join("")),
+        }
+    }
+}
+
+/// Conditions that all have to be true for a row to be shown
+#[derive(Clone, Default, Debug)]
+pub struct Filter {
+    pub conditions: Vec<Condition>,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Add a condition, unless the same condition is already there
+    pub fn add(&mut self, condition: Condition) {
+        if !self.conditions.contains(&condition) {
+            self.conditions.push(condition);
+        }
+    }
+
+    /// The WHERE clause, empty if there are no conditions
+    pub fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            return String::new();
+        }
+        let conditions: Vec<String> = self.conditions.iter().map(|c| c.sql()).collect();
+        format!(" WHERE {}", conditions.join(" AND "))
+    }
+
+    /// Filter the rows of any query
+    pub fn apply(&self, query: &str) -> String {
+        if self.conditions.is_empty() {
+            return query.to_owned();
+        }
+        let query = query.trim().trim_end_matches(';');
+        format!("SELECT * FROM ({}){}", query, self.where_clause())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::types::Value;
+
+    use super::{Condition, Filter, Op};
+
+    #[test]
+    fn test_parse() {
+        let columns = vec!["id".to_owned(), "first name".to_owned(), "first".to_owned()];
+        let c = Condition::parse("first name like 'a%'", &columns).unwrap();
+        assert_eq!((c.column.as_str(), c.op), ("first name", Op::Like));
+        assert_eq!(c.values, [Value::Text("a%".to_owned())]);
+        let c = Condition::parse("ID in (1, 2,'3')", &columns).unwrap();
+        assert_eq!(c.values, [Value::Integer(1), Value::Integer(2), Value::Text("3".to_owned())]);
+        assert_eq!(c.sql(), "\"id\" IN (1, 2, '3')");
+        let c = Condition::parse("first in ('a,b', \"c\")", &columns).unwrap();
+        assert_eq!(c.values, [Value::Text("a,b".to_owned()), Value::Text("c".to_owned())]);
+        assert!(Condition::parse("id IS NULL", &columns).is_ok());
+        let c = Condition::parse("id >= 5", &columns).unwrap();
+        assert_eq!(c.sql(), "\"id\" >= 5");
+        assert!(Condition::parse("id =", &columns).is_err());
+        assert!(Condition::parse("age > 1", &columns).is_err());
+        assert!(Condition::parse("idx > 1", &columns).is_err());
+        assert!(Condition::parse("id ~ 1", &columns).is_err());
+
+        let mut filter = Filter::default();
+        filter.add(Condition::is("id", &Value::Integer(1)));
+        filter.add(Condition::is_not("first", &Value::Null));
+        assert_eq!(filter.apply("SELECT * FROM t;"), "SELECT * FROM (SELECT * FROM t) WHERE \"id\" = 1 AND \"first\" IS NOT NULL");
+    }
+}
@@ -0,0 +1,250 @@
+use ratatui::{prelude::{Backend, Constraint}, Frame, widgets::{Block, Borders, Cell, Clear, Row, Table, TableState}};
+use rusqlite::{Connection, OptionalExtension, types::Value};
+use tui_input::{Input, InputRequest};
+
+use super::error::FormError;
+use super::format;
+use super::import::Affinity;
+use super::popup::centered_rect;
+use super::schema::{rowid_alias, table_info, Column};
+use super::sql::{quote, TableName};
+use super::theme::Theme;
+
+/// A column of the row that is inserted
+pub struct Field {
+    pub column: Column,
+    /// The column is the rowid, sqlite picks the next one if the field is empty
+    pub auto: bool,
+    pub input: Input,
+    /// The value the field was filled with, used as it is until the text is changed
+    original: Option<Value>,
+}
+
+impl Field {
+    /// A value has to be typed in the field
+    pub fn required(&self) -> bool {
+        self.column.notnull && self.column.default.is_none() && !self.auto
+    }
+
+    /// The type, key and default of the column
+    pub fn hint(&self, autoincrement: bool) -> String {
+        let mut hint = Vec::new();
+        if !self.column.kind.is_empty() {
+            hint.push(self.column.kind.clone());
+        }
+        if self.column.pk > 0 {
+            hint.push("primary key".to_owned());
+        }
+        if self.auto {
+            hint.push(if autoincrement { "autoincrement" } else { "auto" }.to_owned());
+        }
+        if self.column.notnull {
+            hint.push("not null".to_owned());
+        }
+        if let Some(ref default) = self.column.default {
+            hint.push(format!("default {}", default));
+        }
+        hint.join(" ")
+    }
+
+    /// The value to insert, None leaves it to sqlite
+    fn value(&self) -> Result<Option<Value>, String> {
+        let text = self.input.value();
+        match self.original {
+            Some(ref v) if format::text(v) == text => return Ok(Some(v.clone())),
+            _ => {}
+        }
+        if text.is_empty() {
+            return Ok(match self.auto || self.column.default.is_some() {
+                true => None,
+                false => Some(Value::Null),
+            });
+        }
+        Affinity::from_type(&self.column.kind).convert(Value::Text(text.to_owned())).map(Some)
+    }
+}
+
+/// A popup with a field for every column of a table, to insert a row
+pub struct Form {
+    pub table: TableName,
+    pub fields: Vec<Field>,
+    /// The table is declared with AUTOINCREMENT
+    autoincrement: bool,
+    state: TableState,
+}
+
+impl Form {
+    pub fn new(conn: &Connection, table: &TableName) -> rusqlite::Result<Self> {
+        let columns = table_info(conn, table)?;
+        // only a single INTEGER PRIMARY KEY is the rowid
+        let keys = columns.iter().filter(|c| c.pk > 0).count();
+        let fields = columns.into_iter().map(|column| Field {
+            auto: keys == 1 && column.pk > 0 && column.kind.eq_ignore_ascii_case("INTEGER"),
+            column,
+            input: Input::default(),
+            original: None,
+        }).collect();
+        let master = match table.schema {
+            Some(ref schema) => format!("{}.sqlite_master", quote(schema)),
+            None => "sqlite_master".to_owned(),
+        };
+        let sql: Option<Option<String>> = conn.query_row(
+            &format!("SELECT sql FROM {} WHERE type = 'table' AND name = ?", master),
+            [&table.name],
+            |r| r.get(0),
+        ).optional()?;
+        let autoincrement = sql.flatten().is_some_and(|sql| sql.to_uppercase().contains("AUTOINCREMENT"));
+        let mut state = TableState::default();
+        state.select(Some(0));
+        Ok(Form {
+            table: table.clone(),
+            fields,
+            autoincrement,
+            state,
+        })
+    }
+
+    /// Start from the values of a row, the rowid is left empty to get a new one
+    pub fn fill(&mut self, scheme: &[String], row: &[Value]) {
+        for field in self.fields.iter_mut().filter(|f| !f.auto) {
+            if let Some(v) = scheme.iter().position(|c| *c == field.column.name).and_then(|i| row.get(i)) {
+                field.input = Input::new(format::text(v));
+                field.original = Some(v.clone());
+            }
+        }
+    }
+
+    fn selected(&self) -> usize {
+        self.state.selected().unwrap_or(0)
+    }
+
+    pub fn next(&mut self) {
+        let i = (self.selected() + 1) % self.fields.len().max(1);
+        self.state.select(Some(i));
+    }
+
+    pub fn prev(&mut self) {
+        let i = self.selected().checked_sub(1).unwrap_or(self.fields.len().saturating_sub(1));
+        self.state.select(Some(i));
+    }
+
+    /// Edit the selected field
+    pub fn handle(&mut self, req: InputRequest) {
+        let i = self.selected();
+        if let Some(field) = self.fields.get_mut(i) {
+            field.input.handle(req);
+        }
+    }
+
+    /// Insert the row, returning its rowid, None for WITHOUT ROWID tables
+    pub fn insert(&self, conn: &Connection) -> Result<Option<i64>, FormError> {
+        let mut names = Vec::new();
+        let mut values = Vec::new();
+        for field in self.fields.iter() {
+            let value = field.value().map_err(|e| FormError::Value(field.column.name.clone(), e))?;
+            if let Some(value) = value {
+                names.push(quote(&field.column.name));
+                values.push(value);
+            }
+        }
+        let sql = match names.len() {
+            0 => format!("INSERT INTO {} DEFAULT VALUES", self.table.sql()),
+            n => format!("INSERT INTO {} ({}) VALUES ({})", self.table.sql(), names.join(", "), vec!["?"; n].join(", ")),
+        };
+        conn.execute(&sql, rusqlite::params_from_iter(values))?;
+        Ok(rowid_alias(conn, &self.table)?.map(|_| conn.last_insert_rowid()))
+    }
+
+    /// The primary key of the row as typed, like "a = 1, b = x"
+    pub fn key(&self) -> String {
+        let mut keys: Vec<&Field> = self.fields.iter().filter(|f| f.column.pk > 0).collect();
+        keys.sort_by_key(|f| f.column.pk);
+        let keys: Vec<String> = keys.iter().map(|f| format!("{} = {}", f.column.name, f.input.value())).collect();
+        keys.join(", ")
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, theme: &Theme) {
+        let mut area = centered_rect(80, 80, f.size());
+        area.height = area.height.min(self.fields.len() as u16 + 2);
+        let inner = area.width.saturating_sub(2);
+        let hints: Vec<String> = self.fields.iter().map(|field| field.hint(self.autoincrement)).collect();
+        // required fields are marked with a *
+        let names: Vec<String> = self.fields.iter()
+            .map(|field| format!("{}{}", field.column.name, if field.required() { "*" } else { "" }))
+            .collect();
+        let namew = names.iter().map(|n| n.chars().count()).max().unwrap_or(0).min(inner as usize / 3) as u16;
+        let hintw = hints.iter().map(|h| h.chars().count()).max().unwrap_or(0).min(inner as usize / 3) as u16;
+        let valuew = inner.saturating_sub(namew + hintw + 2).max(1);
+
+        let rows = self.fields.iter().zip(names).zip(hints).map(|((field, name), hint)| {
+            let scroll = field.input.visual_scroll(valuew as usize);
+            let value: String = field.input.value().chars().skip(scroll).collect();
+            Row::new(vec![
+                Cell::from(name).style(theme.header),
+                Cell::from(value).style(theme.input),
+                Cell::from(hint).style(theme.null),
+            ])
+        });
+        let widths = [Constraint::Length(namew), Constraint::Length(valuew), Constraint::Length(hintw)];
+        let block = Block::default()
+            .title(format!("Insert into {}", self.table))
+            .title_style(theme.header)
+            .borders(Borders::ALL);
+        let t = Table::new(rows)
+            .block(block)
+            .column_spacing(1)
+            .highlight_style(theme.hlrow)
+            .widths(&widths);
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(t, area, &mut self.state);
+
+        if let Some(field) = self.fields.get(self.selected()) {
+            let scroll = field.input.visual_scroll(valuew as usize);
+            let x = area.x + 1 + namew + 1 + (field.input.visual_cursor() - scroll) as u16;
+            let y = area.y + 1 + (self.selected() - self.state.offset()) as u16;
+            f.set_cursor(x, y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::{Connection, types::Value};
+
+    use super::Form;
+    use crate::sql::TableName;
+
+    #[test]
+    fn test_insert() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t(id INTEGER PRIMARY KEY AUTOINCREMENT, n INT NOT NULL, s TEXT DEFAULT 'x', b BLOB)").unwrap();
+        let table = TableName::new(None, "t");
+        let mut form = Form::new(&conn, &table).unwrap();
+        assert!(form.fields[0].auto && form.fields[1].required());
+        assert_eq!(form.fields[0].hint(form.autoincrement), "INTEGER primary key autoincrement");
+
+        // n is required, and has to be a number
+        assert!(form.insert(&conn).is_err());
+        form.fields[1].input = "seven".into();
+        assert!(form.insert(&conn).is_err());
+        form.fields[1].input = "7".into();
+        assert_eq!(form.insert(&conn).unwrap(), Some(1));
+
+        let scheme: Vec<String> = ["id", "n", "s", "b"].iter().map(|s| s.to_string()).collect();
+        let row = vec![Value::Integer(1), Value::Integer(7), Value::Text("y".to_owned()), Value::Blob(vec![0, 1])];
+        let mut form = Form::new(&conn, &table).unwrap();
+        form.fill(&scheme, &row);
+        assert_eq!(form.insert(&conn).unwrap(), Some(2));
+        let copy: (i64, String, Vec<u8>) = conn.query_row("SELECT n, s, b FROM t WHERE id = 2", [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))).unwrap();
+        assert_eq!(copy, (7, "y".to_owned(), vec![0, 1]));
+        let s: String = conn.query_row("SELECT s FROM t WHERE id = 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(s, "x");
+
+        conn.execute_batch("CREATE TABLE k(a, b, PRIMARY KEY (a, b)) WITHOUT ROWID").unwrap();
+        let mut form = Form::new(&conn, &TableName::new(None, "k")).unwrap();
+        form.fields[0].input = "1".into();
+        form.fields[1].input = "x".into();
+        assert_eq!(form.insert(&conn).unwrap(), None);
+        assert_eq!(form.key(), "a = 1, b = x");
+    }
+}
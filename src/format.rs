@@ -0,0 +1,109 @@
+use rusqlite::types::Value;
+
+use crate::export::hex;
+
+/// Number of bytes of a blob shown in a cell
+const PREVIEW: usize = 8;
+
+/// The value as it is stored, for editing and searching
+pub fn text(v: &Value) -> String {
+    match v {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(t) => t.clone(),
+        Value::Blob(b) => hex(b),
+    }
+}
+
+/// A size in bytes, like 1.5 KiB
+pub fn size(len: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = len as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", len),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
+/// Parse text that looks like a json object or array
+fn json(t: &str) -> Option<serde_json::Value> {
+    let t = t.trim_start();
+    if !t.starts_with(['{', '[']) {
+        return None;
+    }
+    serde_json::from_str(t).ok()
+}
+
+/// The value as it's shown in a cell of the table
+pub fn show(v: &Value) -> String {
+    match v {
+        Value::Null => "NULL".to_owned(),
+        Value::Text(t) => match json(t) {
+            Some(json) => json.to_string(),
+            None => t.clone(),
+        },
+        Value::Blob(b) => {
+            let more = if b.len() > PREVIEW { "…" } else { "" };
+            format!("<{}> {}{}", size(b.len()), hex(&b[..b.len().min(PREVIEW)]), more)
+        }
+        v => text(v),
+    }
+}
+
+/// The value wrapped to lines of width, json is pretty printed
+pub fn multiline(v: &Value, width: usize) -> String {
+    let text = match v {
+        Value::Text(t) => match json(t) {
+            Some(json) => serde_json::to_string_pretty(&json).unwrap_or_else(|_| t.clone()),
+            None => t.clone(),
+        },
+        v => show(v),
+    };
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        for chunk in chars.chunks(width) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Numbers are right-aligned in their column
+pub fn is_numeric(v: &Value) -> bool {
+    matches!(v, Value::Integer(_) | Value::Real(_))
+}
+
+/// Pad text on the left so it ends at the right of a column of width
+pub fn align_right(text: &str, width: usize) -> String {
+    format!("{:>w$}", text, w = width)
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::types::Value;
+
+    use super::{multiline, show, size};
+
+    #[test]
+    fn test_show() {
+        assert_eq!(show(&Value::Null), "NULL");
+        assert_eq!(show(&Value::Text("Null".to_owned())), "Null");
+        assert_eq!(show(&Value::Text("{ \"a\": [1, 2] }".to_owned())), "{\"a\":[1,2]}");
+        assert_eq!(show(&Value::Text("{ not json".to_owned())), "{ not json");
+        assert_eq!(show(&Value::Blob(vec![0xde, 0xad])), "<2 B> dead");
+        assert_eq!(show(&Value::Blob(vec![0; 2048])), "<2.0 KiB> 0000000000000000…");
+        assert_eq!(size(1536), "1.5 KiB");
+        assert_eq!(multiline(&Value::Text("abcde".to_owned()), 2), "ab\ncd\ne");
+    }
+}
@@ -0,0 +1,119 @@
+use regex::{Regex, RegexBuilder};
+
+#[derive(Clone, Copy, Default)]
+pub struct GrepOpts {
+    pub ignore_case: bool,
+    pub regex: bool,
+}
+
+impl GrepOpts {
+    /// A short label for the prompt, like "[re][i]"
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.regex {
+            label.push_str("[re]");
+        }
+        if self.ignore_case {
+            label.push_str("[i]");
+        }
+        label
+    }
+}
+
+#[derive(Clone)]
+pub enum Matcher {
+    Plain(String, bool),
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, opts: GrepOpts) -> Result<Self, regex::Error> {
+        if opts.regex {
+            let re = RegexBuilder::new(pattern)
+                .case_insensitive(opts.ignore_case)
+                .build()?;
+            Ok(Matcher::Regex(re))
+        } else if opts.ignore_case {
+            Ok(Matcher::Plain(pattern.to_lowercase(), true))
+        } else {
+            Ok(Matcher::Plain(pattern.to_owned(), false))
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Plain(pattern, true) => text.to_lowercase().contains(pattern.as_str()),
+            Matcher::Plain(pattern, false) => text.contains(pattern.as_str()),
+            Matcher::Regex(re) => re.is_match(text),
+        }
+    }
+}
+
+/// The result of a search in a table
+#[derive(Clone)]
+pub struct Grep {
+    pub pattern: String,
+    pub opts: GrepOpts,
+    /// Matching cells as (row, column), sorted
+    pub matches: Vec<(usize, usize)>,
+    pub current: Option<usize>,
+}
+
+impl Grep {
+    pub fn is_match(&self, row: usize, col: usize) -> bool {
+        self.matches.binary_search(&(row, col)).is_ok()
+    }
+
+    /// Move to the next match after row, wrapping around
+    pub fn next(&mut self, row: usize) -> Option<(usize, usize)> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let i = match self.current {
+            Some(i) => (i + 1) % self.matches.len(),
+            None => self.matches.iter().position(|m| m.0 >= row).unwrap_or(0),
+        };
+        self.current = Some(i);
+        Some(self.matches[i])
+    }
+
+    /// Move to the previous match before row, wrapping around
+    pub fn prev(&mut self, row: usize) -> Option<(usize, usize)> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let i = match self.current {
+            Some(0) => self.matches.len() - 1,
+            Some(i) => i - 1,
+            None => self.matches.iter().rposition(|m| m.0 <= row).unwrap_or(self.matches.len() - 1),
+        };
+        self.current = Some(i);
+        Some(self.matches[i])
+    }
+
+    pub fn status(&self) -> String {
+        match self.current {
+            Some(i) => format!("match {}/{}", i + 1, self.matches.len()),
+            None => format!("{} matches", self.matches.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GrepOpts, Matcher};
+
+    #[test]
+    fn test_matcher() {
+        let plain = Matcher::new("Foo", GrepOpts::default()).unwrap();
+        assert!(plain.is_match("a Foo b"));
+        assert!(!plain.is_match("a foo b"));
+
+        let nocase = Matcher::new("Foo", GrepOpts { ignore_case: true, regex: false }).unwrap();
+        assert!(nocase.is_match("a fOO b"));
+
+        let re = Matcher::new("^f.o$", GrepOpts { ignore_case: true, regex: true }).unwrap();
+        assert!(re.is_match("FOO"));
+        assert!(!re.is_match("a foo"));
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// Sql that was run, a line in the history file
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Entry {
+    /// Seconds since the unix epoch
+    pub time: i64,
+    /// The path of the main database
+    pub database: String,
+    /// query or exec
+    pub kind: String,
+    pub sql: String,
+    /// The sql ran without an error
+    pub ok: bool,
+}
+
+impl Entry {
+    /// The entry as a command for the input line, only entries on one line fit in it
+    pub fn command(&self) -> String {
+        format!("{} {}", self.kind, self.sql)
+    }
+
+    /// Joining the lines could change the sql, a -- comment would hide the lines after it
+    pub fn is_multiline(&self) -> bool {
+        self.sql.contains('\n')
+    }
+
+    /// The local time the sql was run, like 2024-01-31 12:00:00
+    pub fn date(&self, conn: &Connection) -> rusqlite::Result<String> {
+        let mut stmt = conn.prepare_cached("SELECT datetime(?, 'unixepoch', 'localtime')")?;
+        stmt.query_row([self.time], |r| r.get(0))
+    }
+}
+
+/// The query and exec commands run on a database, oldest first
+pub struct History {
+    /// The file entries are added to, shared by every database, None keeps them in memory
+    path: Option<PathBuf>,
+    database: String,
+    /// The most entries kept of a database
+    size: usize,
+    /// Sql that is run again replaces the older entry
+    pub entries: Vec<Entry>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            path: None,
+            database: String::new(),
+            size: usize::MAX,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl History {
+    /// Read the entries of database from the file at path, lines that can't be read are skipped.
+    /// The file is rewritten without them, repeated sql and all but the newest size entries of each database
+    pub fn load(path: PathBuf, database: &str, size: usize) -> io::Result<Self> {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let lines: Vec<&str> = text.lines().collect();
+        let mut seen = HashSet::new();
+        let mut counts = HashMap::new();
+        let mut kept = Vec::new();
+        for entry in lines.iter().rev().filter_map(|l| serde_json::from_str::<Entry>(l).ok()) {
+            let count = counts.entry(entry.database.clone()).or_insert(0);
+            if *count < size && seen.insert((entry.database.clone(), entry.kind.clone(), entry.sql.clone())) {
+                *count += 1;
+                kept.push(entry);
+            }
+        }
+        kept.reverse();
+        if kept.len() < lines.len() {
+            let mut text = String::new();
+            for entry in kept.iter() {
+                text.push_str(&serde_json::to_string(entry)?);
+                text.push('\n');
+            }
+            // a new file replaces the old one, so a failed write doesn't lose the history
+            let tmp = path.with_extension("ndjson.tmp");
+            fs::write(&tmp, text)?;
+            fs::rename(&tmp, &path)?;
+        }
+        Ok(History {
+            path: Some(path),
+            database: database.to_owned(),
+            size,
+            entries: kept.into_iter().filter(|e| e.database == database).collect(),
+        })
+    }
+
+    fn push(&mut self, entry: Entry) {
+        self.entries.retain(|e| e.kind != entry.kind || e.sql != entry.sql);
+        self.entries.push(entry);
+        if self.entries.len() > self.size {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Add sql that was run and append it to the file
+    pub fn add(&mut self, kind: &str, sql: &str, ok: bool) -> io::Result<()> {
+        if sql.trim().is_empty() {
+            return Ok(());
+        }
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+        let entry = Entry {
+            time,
+            database: self.database.clone(),
+            kind: kind.to_owned(),
+            sql: sql.to_owned(),
+            ok,
+        };
+        if let Some(ref path) = self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        self.push(entry);
+        Ok(())
+    }
+}
+
+pub enum Recalled<'a, D> {
+    Entry(&'a Entry),
+    /// Back past the newest entry to what was typed
+    Draft(D),
+}
+
+/// Going through the history with up and down, D is what was typed before
+pub struct Recall<D> {
+    index: Option<usize>,
+    draft: Option<D>,
+    /// The entries that can be recalled, the others are skipped
+    keep: fn(&Entry) -> bool,
+}
+
+impl<D> Default for Recall<D> {
+    fn default() -> Self {
+        Recall::only(|_| true)
+    }
+}
+
+impl<D> Recall<D> {
+    /// Only recall the entries keep is true for
+    pub fn only(keep: fn(&Entry) -> bool) -> Self {
+        Recall { index: None, draft: None, keep }
+    }
+
+    /// The entry before the one shown, draft is kept when leaving what was typed
+    pub fn prev<'a, F: FnOnce() -> D>(&mut self, history: &'a History, draft: F) -> Option<&'a Entry> {
+        let end = self.index.unwrap_or(history.entries.len());
+        let index = history.entries[..end].iter().rposition(self.keep)?;
+        if self.index.is_none() {
+            self.draft = Some(draft());
+        }
+        self.index = Some(index);
+        history.entries.get(index)
+    }
+
+    /// The entry after the one shown, or the draft after the newest
+    pub fn next<'a>(&mut self, history: &'a History) -> Option<Recalled<'a, D>> {
+        let start = self.index? + 1;
+        if let Some(i) = history.entries[start..].iter().position(self.keep) {
+            self.index = Some(start + i);
+            return Some(Recalled::Entry(&history.entries[start + i]));
+        }
+        self.index = None;
+        self.draft.take().map(Recalled::Draft)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{History, Recall, Recalled};
+
+    #[test]
+    fn test_history() {
+        let path = std::env::temp_dir().join(format!("qb-history-{}.ndjson", std::process::id()));
+        let mut history = History::load(path.clone(), "a.db", 2).unwrap();
+        history.add("query", "SELECT 0", true).unwrap();
+        history.add("query", "SELECT 1", true).unwrap();
+        history.add("exec", "DELETE FROM nope", false).unwrap();
+        history.add("query", "SELECT 1", true).unwrap();
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+        let mut other = History::load(path.clone(), "b.db", 2).unwrap();
+        other.add("query", "SELECT 2", true).unwrap();
+
+        let history = History::load(path.clone(), "a.db", 2).unwrap();
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(lines, 3);
+        let sql: Vec<&str> = history.entries.iter().map(|e| e.sql.as_str()).collect();
+        assert_eq!(sql, ["DELETE FROM nope", "SELECT 1"]);
+        assert!(!history.entries[0].ok);
+
+        let mut recall = Recall::default();
+        assert_eq!(recall.prev(&history, || "typed").unwrap().sql, "SELECT 1");
+        assert_eq!(recall.prev(&history, || "typed").unwrap().sql, "DELETE FROM nope");
+        assert!(recall.prev(&history, || "typed").is_none());
+        assert!(matches!(recall.next(&history), Some(Recalled::Entry(e)) if e.sql == "SELECT 1"));
+        assert!(matches!(recall.next(&history), Some(Recalled::Draft("typed"))));
+        assert!(recall.next(&history).is_none());
+
+        let mut history = History::default();
+        history.add("query", "SELECT 1", true).unwrap();
+        history.add("exec", "DELETE FROM t -- all\nWHERE a", true).unwrap();
+        let mut recall = Recall::only(|e| !e.is_multiline());
+        assert_eq!(recall.prev(&history, || "typed").unwrap().sql, "SELECT 1");
+        assert!(matches!(recall.next(&history), Some(Recalled::Draft("typed"))));
+    }
+}
@@ -0,0 +1,357 @@
+use std::{fmt::Display, fs::File, io::{BufRead, BufReader, Read}, path::Path};
+
+use rusqlite::{Connection, types::Value};
+use serde_json::Map;
+
+use crate::error::ImportError;
+use crate::export::Format;
+use crate::schema::table_info;
+use crate::sql::{quote, TableName};
+
+/// Number of rows used to guess the type of the columns
+const SAMPLE: usize = 100;
+
+/// The type affinity of a column, see https://www.sqlite.org/datatype3.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Affinity {
+    Integer,
+    Real,
+    Numeric,
+    Text,
+    Blob,
+}
+
+impl Affinity {
+    /// The affinity sqlite gives a declared column type
+    pub fn from_type(kind: &str) -> Self {
+        let kind = kind.to_uppercase();
+        if kind.contains("INT") {
+            Affinity::Integer
+        } else if kind.contains("CHAR") || kind.contains("CLOB") || kind.contains("TEXT") {
+            Affinity::Text
+        } else if kind.is_empty() || kind.contains("BLOB") {
+            Affinity::Blob
+        } else if kind.contains("REAL") || kind.contains("FLOA") || kind.contains("DOUB") {
+            Affinity::Real
+        } else {
+            Affinity::Numeric
+        }
+    }
+
+    /// Guess the affinity of a column from some of its values
+    fn infer<'a>(values: impl Iterator<Item = &'a Value>) -> Self {
+        let mut affinity = None;
+        for v in values {
+            let kind = match v {
+                Value::Null => continue,
+                Value::Integer(_) => Affinity::Integer,
+                Value::Real(_) => Affinity::Real,
+                Value::Blob(_) => Affinity::Blob,
+                Value::Text(t) if t.parse::<i64>().is_ok() => Affinity::Integer,
+                Value::Text(t) if t.parse::<f64>().is_ok() => Affinity::Real,
+                Value::Text(_) => Affinity::Text,
+            };
+            affinity = match (affinity, kind) {
+                (None, kind) => Some(kind),
+                (Some(a), b) if a == b => Some(a),
+                (Some(Affinity::Integer), Affinity::Real) | (Some(Affinity::Real), Affinity::Integer) => Some(Affinity::Real),
+                _ => return Affinity::Text,
+            };
+        }
+        affinity.unwrap_or(Affinity::Text)
+    }
+
+    /// Convert a value read from a file to a value of the column
+    pub fn convert(self, v: Value) -> Result<Value, String> {
+        let text = match v {
+            Value::Text(ref t) => t.trim(),
+            _ => return Ok(v),
+        };
+        let int = text.parse::<i64>().map(Value::Integer);
+        let real = text.parse::<f64>().map(Value::Real);
+        match self {
+            Affinity::Integer => int.or(real).map_err(|_| format!("'{}' is not an integer", text)),
+            Affinity::Real => real.map_err(|_| format!("'{}' is not a number", text)),
+            Affinity::Numeric => Ok(int.or(real).unwrap_or(v)),
+            Affinity::Text | Affinity::Blob => Ok(v),
+        }
+    }
+}
+
+impl Display for Affinity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Affinity::Integer => write!(f, "INTEGER"),
+            Affinity::Real => write!(f, "REAL"),
+            Affinity::Numeric => write!(f, "NUMERIC"),
+            Affinity::Text => write!(f, "TEXT"),
+            Affinity::Blob => write!(f, "BLOB"),
+        }
+    }
+}
+
+/// A row that couldn't be imported, row is 1 for the first row of data
+#[derive(Debug)]
+pub struct RowError {
+    pub row: usize,
+    pub message: String,
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {}: {}", self.row, self.message)
+    }
+}
+
+/// The result of an import
+pub struct Import {
+    pub rows: usize,
+    /// The table didn't exist before the import
+    pub created: bool,
+    pub errors: Vec<RowError>,
+}
+
+type Record = Result<Vec<Value>, String>;
+
+/// Columns and rows read from a file
+struct Source<'a> {
+    columns: Vec<String>,
+    records: Box<dyn Iterator<Item = Record> + 'a>,
+}
+
+fn csv_source<'a, R: Read + 'a>(r: R, delimiter: u8) -> Result<Source<'a>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .quoting(delimiter != b'\t')
+        .from_reader(r);
+    let columns = reader.headers()?.iter().map(|s| s.to_owned()).collect();
+    let tsv = delimiter == b'\t';
+    let records = reader.into_records().map(move |record| {
+        let record = record.map_err(|e| e.to_string())?;
+        Ok(record.iter().map(|field| match field {
+            // empty fields and \N are how csv and tsv exports write NULL
+            "" => Value::Null,
+            "\\N" if tsv => Value::Null,
+            _ if tsv => Value::Text(unescape(field)),
+            _ => Value::Text(field.to_owned()),
+        }).collect())
+    });
+    Ok(Source {
+        columns,
+        records: Box::new(records),
+    })
+}
+
+/// Undo the escaping of tabs, newlines and backslashes in a tsv field
+fn unescape(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn json_value(v: serde_json::Value) -> Value {
+    match v {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(b.into()),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s),
+        // nested values are kept as json text
+        v => Value::Text(v.to_string()),
+    }
+}
+
+/// Read ndjson, the columns are the keys of the objects in the first rows
+fn ndjson_source<'a, R: Read + 'a>(r: R) -> Result<Source<'a>, ImportError> {
+    let mut lines = BufReader::new(r).lines()
+        .filter(|l| !l.as_ref().is_ok_and(|l| l.trim().is_empty()));
+    let mut sample = Vec::new();
+    for line in lines.by_ref().take(SAMPLE) {
+        sample.push(serde_json::from_str::<Map<String, serde_json::Value>>(&line?).map_err(|e| e.to_string()));
+    }
+    let mut columns: Vec<String> = Vec::new();
+    for object in sample.iter().flatten() {
+        for key in object.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+
+    let rest = lines.map(|line| {
+        let line = line.map_err(|e| e.to_string())?;
+        serde_json::from_str::<Map<String, serde_json::Value>>(&line).map_err(|e| e.to_string())
+    });
+    let names = columns.clone();
+    let records = sample.into_iter().chain(rest).map(move |object| {
+        let mut object = object?;
+        let row = names.iter().map(|c| object.remove(c).map_or(Value::Null, json_value)).collect();
+        match object.keys().next() {
+            Some(key) => Err(format!("unknown column {}", key)),
+            None => Ok(row),
+        }
+    });
+    Ok(Source {
+        columns,
+        records: Box::new(records),
+    })
+}
+
+/// The format of a file from its extension, csv if it isn't known
+pub fn format(path: &Path) -> Format {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("tsv") | Some("tab") => Format::Tsv,
+        Some("ndjson") | Some("jsonl") => Format::Ndjson,
+        _ => Format::Csv,
+    }
+}
+
+/// Import a csv, tsv or ndjson file into table, creating it if it doesn't exist
+pub fn import(conn: &Connection, path: &Path, table: &TableName) -> Result<Import, ImportError> {
+    let file = File::open(path)?;
+    import_from(conn, file, format(path), table)
+}
+
+/// Import rows into table inside a savepoint, rows that can't be converted or
+/// inserted are skipped and returned as errors
+pub fn import_from<R: Read>(conn: &Connection, r: R, format: Format, table: &TableName) -> Result<Import, ImportError> {
+    let source = match format {
+        Format::Csv => csv_source(r, b',')?,
+        Format::Tsv => csv_source(r, b'\t')?,
+        Format::Ndjson => ndjson_source(r)?,
+        _ => return Err(ImportError::Format(format)),
+    };
+    if source.columns.is_empty() {
+        return Err(ImportError::NoColumns);
+    }
+
+    // A savepoint works both inside and outside of a transaction
+    conn.execute_batch("SAVEPOINT import")?;
+    let res = insert(conn, source, table);
+    match res {
+        Ok(_) => conn.execute_batch("RELEASE import")?,
+        Err(_) => conn.execute_batch("ROLLBACK TO import; RELEASE import")?,
+    }
+    res
+}
+
+fn insert(conn: &Connection, source: Source, table: &TableName) -> Result<Import, ImportError> {
+    let Source { columns, mut records } = source;
+    let mut sample = Vec::new();
+    for record in records.by_ref().take(SAMPLE) {
+        sample.push(record);
+    }
+
+    let existing = table_info(conn, table)?;
+    let created = existing.is_empty();
+    let affinities: Vec<Affinity> = if created {
+        let affinities: Vec<Affinity> = (0..columns.len())
+            .map(|i| Affinity::infer(sample.iter().flatten().filter_map(|r| r.get(i))))
+            .collect();
+        let defs: Vec<String> = columns.iter().zip(affinities.iter())
+            .map(|(c, a)| format!("{} {}", quote(c), a))
+            .collect();
+        conn.execute_batch(&format!("CREATE TABLE {} ({})", table.sql(), defs.join(", ")))?;
+        affinities
+    } else {
+        columns.iter().map(|c| {
+            existing.iter()
+                .find(|col| col.name.eq_ignore_ascii_case(c))
+                .map(|col| Affinity::from_type(&col.kind))
+                .ok_or_else(|| ImportError::Column(c.clone(), table.to_string()))
+        }).collect::<Result<_, _>>()?
+    };
+
+    let names: Vec<String> = columns.iter().map(|c| quote(c)).collect();
+    let params = vec!["?"; columns.len()].join(", ");
+    let sql = format!("INSERT INTO {} ({}) VALUES ({})", table.sql(), names.join(", "), params);
+    let mut stmt = conn.prepare(&sql)?;
+
+    let mut import = Import {
+        rows: 0,
+        created,
+        errors: Vec::new(),
+    };
+    for (i, record) in sample.into_iter().chain(records).enumerate() {
+        let row = record.and_then(|values| {
+            if values.len() != columns.len() {
+                return Err(format!("expected {} fields, found {}", columns.len(), values.len()));
+            }
+            values.into_iter().zip(affinities.iter())
+                .map(|(v, a)| a.convert(v))
+                .collect::<Result<Vec<Value>, String>>()
+        });
+        let res = row.and_then(|row| {
+            stmt.execute(rusqlite::params_from_iter(row)).map_err(|e| e.to_string())
+        });
+        match res {
+            Ok(_) => import.rows += 1,
+            Err(message) => import.errors.push(RowError { row: i + 1, message }),
+        }
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::{Connection, types::Value};
+
+    use super::{import_from, Affinity};
+    use crate::export::Format;
+    use crate::sql::TableName;
+
+    #[test]
+    fn test_import() {
+        let conn = Connection::open_in_memory().unwrap();
+        let table = TableName::new(None, "t");
+        let csv = "id,name,score\n1,a,1.5\n2,,2\n";
+        let import = import_from(&conn, csv.as_bytes(), Format::Csv, &table).unwrap();
+        assert!(import.created);
+        assert_eq!(import.rows, 2);
+        assert!(import.errors.is_empty());
+
+        let kinds: Vec<String> = conn.prepare("SELECT type FROM pragma_table_info('t')").unwrap()
+            .query_map([], |r| r.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(kinds, ["INTEGER", "TEXT", "REAL"]);
+        let name: Value = conn.query_row("SELECT name FROM t WHERE id = 2", [], |r| r.get(0)).unwrap();
+        assert_eq!(name, Value::Null);
+
+        // rows that don't fit the columns are skipped
+        let csv = "id,score\n3,x\n4,4\n5\n";
+        let import = import_from(&conn, csv.as_bytes(), Format::Csv, &table).unwrap();
+        assert_eq!(import.rows, 1);
+        assert_eq!(import.errors.len(), 2);
+        assert_eq!(import.errors[0].row, 1);
+
+        // into the existing table, ndjson keys are matched to the columns
+        let ndjson = "{\"score\": 4, \"id\": 4}\n\n{\"id\": 5, \"name\": \"e\", \"other\": 1}\n";
+        let import = import_from(&conn, ndjson.as_bytes(), Format::Ndjson, &table);
+        assert!(import.is_err());
+        let ndjson = "{\"score\": 4, \"id\": 4}\n\n{\"id\": 5, \"name\": \"e\"}\n";
+        let import = import_from(&conn, ndjson.as_bytes(), Format::Ndjson, &table).unwrap();
+        assert!(!import.created);
+        assert_eq!(import.rows, 2);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 5);
+
+        assert_eq!(Affinity::from_type("VARCHAR(10)"), Affinity::Text);
+        assert_eq!(Affinity::from_type("BIGINT"), Affinity::Integer);
+        assert_eq!(Affinity::from_type(""), Affinity::Blob);
+    }
+}
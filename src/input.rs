@@ -3,11 +3,32 @@ use std::{fmt::Display, convert::TryFrom};
 use ratatui::{prelude::{Rect, Backend}, Frame, widgets::Paragraph};
 use tui_input::InputRequest;
 
+use crate::complete::{Completion, Completions};
 use crate::error::ConvertError;
+use crate::history::{History, Recall, Recalled};
+use crate::saved::Run;
+use crate::grep::GrepOpts;
+use crate::theme::Theme;
 
 pub enum InputType {
     Exec,
     Query,
+    Attach,
+    Export,
+    Import,
+    /// Edit the cell at (row, column) of the selected table
+    Edit(usize, usize),
+    Search(GrepOpts),
+    /// Add a condition to the filter of the selected table
+    Filter,
+    /// Set the cells in the columns of the rows with the rowids to one value
+    Set(Vec<i64>, Vec<usize>),
+    /// Write the blob that is viewed to a file
+    Save,
+    /// Load a file into the blob that is viewed
+    Load,
+    /// A value for the next parameter of a saved query
+    Param(Run),
 }
 
 impl Display for InputType {
@@ -15,6 +36,16 @@ impl Display for InputType {
         match self {
             InputType::Exec => write!(f, "exec"),
             InputType::Query => write!(f, "query"),
+            InputType::Attach => write!(f, "attach"),
+            InputType::Export => write!(f, "export"),
+            InputType::Import => write!(f, "import"),
+            InputType::Edit(_, _) => write!(f, "edit"),
+            InputType::Search(_) => write!(f, "search"),
+            InputType::Filter => write!(f, "filter"),
+            InputType::Set(_, _) => write!(f, "set"),
+            InputType::Save => write!(f, "save"),
+            InputType::Load => write!(f, "load"),
+            InputType::Param(_) => write!(f, "param"),
         }
     }
 }
@@ -26,6 +57,9 @@ impl TryFrom<&str> for InputType {
         match value {
             "exec" => Ok(InputType::Exec),
             "query" => Ok(InputType::Query),
+            "attach" => Ok(InputType::Attach),
+            "export" => Ok(InputType::Export),
+            "import" => Ok(InputType::Import),
             _ => Err(ConvertError::InputTypeError(value.to_owned())),
         }
     }
@@ -34,6 +68,10 @@ impl TryFrom<&str> for InputType {
 pub struct Input {
     pub input: tui_input::Input,
     pub kind: InputType,
+    /// Names the word before the cursor can be completed to
+    pub completion: Option<Completion>,
+    /// Where up and down are in the history
+    pub recall: Recall<String>,
 }
 
 impl Input { 
@@ -41,6 +79,9 @@ impl Input {
         Input {
             input: tui_input::Input::new(value),
             kind,
+            completion: None,
+            // the lines of sql would be joined on the input line
+            recall: Recall::only(|e| !e.is_multiline()),
         }
     }
 
@@ -48,11 +89,74 @@ impl Input {
         self.input.handle(event);
     }
 
-    pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect) { 
-        let input = Paragraph::new(self.input.value());
+    /// Look for completions of the word before the cursor, only in the sql of query and exec commands
+    pub fn update_completion(&mut self, completions: &Completions) {
+        let value = self.input.value().trim_start();
+        let command = value.split_whitespace().next().unwrap_or_default();
+        let before: String = self.input.value().chars().take(self.input.cursor()).collect();
+        let before = before.trim_start();
+        self.completion = match self.kind {
+            InputType::Exec | InputType::Query if matches!(command, "query" | "exec") && before.len() > command.len() => {
+                Completion::new(completions, &value[command.len()..], &before[command.len()..])
+            }
+            _ => None,
+        };
+    }
+
+    /// Show the command run before this one, only query and exec have a history
+    pub fn history_prev(&mut self, history: &History) {
+        if !matches!(self.kind, InputType::Exec | InputType::Query) {
+            return;
+        }
+        let value = self.input.value().to_owned();
+        if let Some(entry) = self.recall.prev(history, || value) {
+            self.input = tui_input::Input::new(entry.command());
+        }
+    }
+
+    /// Show the command run after this one, or what was typed after the newest
+    pub fn history_next(&mut self, history: &History) {
+        match self.recall.next(history) {
+            Some(Recalled::Entry(entry)) => self.input = tui_input::Input::new(entry.command()),
+            Some(Recalled::Draft(value)) => self.input = tui_input::Input::new(value),
+            None => {}
+        }
+    }
+
+    /// Replace the word before the cursor with the selected completion
+    pub fn complete(&mut self) {
+        if let Some(completion) = self.completion.take() {
+            for _ in completion.word.chars() {
+                self.input.handle(InputRequest::DeletePrevChar);
+            }
+            for c in completion.selected().chars() {
+                self.input.handle(InputRequest::InsertChar(c));
+            }
+        }
+    }
+
+    /// Text shown before the value, commands have their kind as part of the value
+    pub fn prompt(&self) -> String {
+        match self.kind {
+            InputType::Search(opts) => format!("{}/", opts.label()),
+            InputType::Filter => "filter: ".to_owned(),
+            InputType::Set(ref rowids, _) => format!("set {} rows to: ", rowids.len()),
+            InputType::Save => "save to: ".to_owned(),
+            InputType::Load => "load from: ".to_owned(),
+            InputType::Param(ref run) => format!("{}, {} = ", run.query.name, run.next().unwrap_or_default()),
+            _ => String::new(),
+        }
+    }
+
+    pub fn render<B: Backend>(&self, f: &mut Frame<B>, area: Rect, theme: &Theme) { 
+        let prompt = self.prompt();
+        let input = Paragraph::new(format!("{}{}", prompt, self.input.value())).style(theme.input);
         f.render_widget(input, area);
-        let x = area.x + self.input.visual_cursor() as u16;
+        let x = area.x + (prompt.chars().count() + self.input.visual_cursor()) as u16;
         let y = area.y;
+        if let Some(ref completion) = self.completion {
+            completion.render(f, (x, y), theme);
+        }
         f.set_cursor(x, y+1);
     }
 }
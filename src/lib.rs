@@ -2,3 +2,24 @@ pub mod error;
 pub mod rows;
 pub mod zoom;
 pub mod input;
+pub mod sql;
+pub mod popup;
+pub mod grep;
+pub mod pager;
+pub mod schema;
+pub mod export;
+pub mod import;
+pub mod theme;
+pub mod format;
+pub mod blob;
+pub mod sort;
+pub mod filter;
+pub mod clipboard;
+pub mod visual;
+pub mod form;
+pub mod delete;
+pub mod editor;
+pub mod complete;
+pub mod history;
+pub mod picker;
+pub mod saved;
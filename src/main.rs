@@ -1,11 +1,12 @@
-use qb::{rows::DbTable, input::{Input, InputType}};
+use qb::{error::{ConvertError, EditError}, rows::{DbTable, parse_value}, format, input::{Input, InputType}, grep::{Grep, GrepOpts}, sql::{quote, TableName}, popup::Confirm, pager::Pager, sort::Sort, filter::{self, Condition, Filter}, schema::Schema, export::{Exporter, Format}, import, clipboard, blob::BlobView, form::Form, editor::Editor, complete::Completions, history::{Entry, History}, picker::Picker, saved::{Run, SavedQuery}, delete::{self, Keys}, schema::{rowid_alias, table_info}, visual::{Kind, Selection}};
 use rusqlite::Connection;
 use rusqlite::types::Value;
-use clap::Parser;
-use std::{path::PathBuf, time::Duration, io::Stdout, convert::TryInto};
+use clap::{Parser, Subcommand};
+use std::{path::{Path, PathBuf}, time::Duration, io::Stdout, convert::TryInto, rc::Rc, ops::RangeInclusive};
 use anyhow::{Context, Result, bail};
 
-use std::io;
+use std::io::{self, Write, BufWriter};
+use std::fs::File;
 
 mod ui;
 mod config;
@@ -18,59 +19,480 @@ use ratatui::{
     Terminal, prelude::Backend
 };
 
-use crossterm::{event::{self, Event, KeyCode}, terminal::{enable_raw_mode, EnterAlternateScreen, disable_raw_mode, LeaveAlternateScreen}, execute};
+use crossterm::{event::{self, Event, KeyCode, KeyModifiers}, terminal::{enable_raw_mode, EnterAlternateScreen, disable_raw_mode, LeaveAlternateScreen}, execute};
 
 pub struct Qb {
-    conn: Connection,
+    conn: Rc<Connection>,
     pub titles: Vec<String>,
+    pub kinds: Vec<TabKind>,
     tables: Vec<Option<DbTable>>,
     pub index: usize,
     mode: Mode,
     // input: Option<Input>,
+    confirm: Option<Confirm<Pending>>,
+    schema: Option<Schema>,
+    blob: Option<BlobView>,
+    /// The row being inserted
+    form: Option<Form>,
+    /// The sql being written in the editor
+    editor: Option<Editor>,
+    /// Table and column names to complete sql with
+    completions: Completions,
+    /// The query and exec commands run on the database
+    history: History,
+    /// The history or the saved queries to pick from
+    picker: Option<Picker<Picked>>,
+    /// Shown in the status line until the next key press
+    message: Option<String>,
+    /// The search, row and column of the selected tab before searching, put back if the search is left
+    search_start: Option<(Option<Grep>, Option<usize>, usize)>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum TabKind {
+    Table(TableName),
+    /// Views are read-only since their rows don't have a rowid
+    View(TableName),
+    /// The result of a query
+    Custom(String),
+}
+
+/// Actions that wait for the user to confirm them
+pub enum Pending {
+    Quit,
+    /// Delete the rows with these keys from the selected table
+    Delete(Keys),
+}
+
+/// What a picker picks from
+pub enum Picked {
+    /// Sql run before, put back in the editor or the input line
+    History(Entry),
+    /// A query from the config to run
+    Saved(SavedQuery),
+}
+
+/// The cells of a visual selection
+pub struct Selected {
+    pub columns: Vec<String>,
+    /// Empty if the rows can't be edited
+    pub rowids: Vec<i64>,
+    pub rows: Vec<Vec<Value>>,
 }
 
 impl Qb {
     pub fn new(conn: Connection) -> Result<Qb> {
-        let tbls = {
-            let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type ='table' AND name NOT LIKE 'sqlite_%';")?;
-            let rows = stmt.query_map([], |row| row.get(0))?;
-
-            let mut tbls = Vec::new();
-            for tbl in rows {
-                let t: String = tbl?;
-                tbls.push(t.clone());
-            }
-            tbls
-        };
-        let tables = vec![None; tbls.len()];
-
-        Ok(Qb {
-            conn,
-            titles: tbls,
-            tables,
+        let mut qb = Qb {
+            conn: Rc::new(conn),
+            titles: Vec::new(),
+            kinds: Vec::new(),
+            tables: Vec::new(),
             index: 0,
             mode: Mode::Main,
             // input: None,
-        })
+            confirm: None,
+            schema: None,
+            blob: None,
+            form: None,
+            editor: None,
+            completions: Completions::default(),
+            history: History::default(),
+            picker: None,
+            message: None,
+            search_start: None,
+        };
+        qb.add_tabs(None)?;
+        qb.refresh_completions();
+        Ok(qb)
+    }
+
+    /// Add a tab for every table and view in the database schema, main if None
+    fn add_tabs(&mut self, schema: Option<&str>) -> Result<()> {
+        let master = match schema {
+            Some(schema) => format!("{}.sqlite_master", quote(schema)),
+            None => "sqlite_master".to_owned(),
+        };
+        let mut stmt = self.conn.prepare(&format!("SELECT name, type FROM {} WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY type = 'view';", master))?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        for tbl in rows {
+            let (t, kind): (String, String) = tbl?;
+            let name = TableName::new(schema, &t);
+            self.titles.push(name.to_string());
+            self.kinds.push(if kind == "view" { TabKind::View(name) } else { TabKind::Table(name) });
+            self.tables.push(None);
+        }
+        Ok(())
+    }
+
+    /// Attach another database file and add tabs for its tables
+    pub fn attach(&mut self, path: &str, alias: &str) -> Result<()> {
+        self.conn.execute("ATTACH DATABASE ? AS ?", [path, alias])?;
+        self.add_tabs(Some(alias))?;
+        self.refresh_completions();
+        Ok(())
+    }
+
+    /// Add sql that was run to the history, an error of the sql comes before one saving it
+    pub fn remember(&mut self, kind: &str, sql: &str, res: Result<()>) -> Result<()> {
+        let saved = self.history.add(kind, sql, res.is_ok());
+        res?;
+        saved.context("Couldn't save the history")
+    }
+
+    /// A picker of the history, newest first
+    fn history_picker(&self) -> Result<Picker<Picked>> {
+        let mut items = Vec::new();
+        for entry in self.history.entries.iter().rev() {
+            let failed = if entry.ok { "" } else { " failed" };
+            let text = format!("{} {}{}: {}", entry.date(&self.conn)?, entry.kind, failed, entry.sql.replace('\n', " "));
+            items.push((text, Picked::History(entry.clone())));
+        }
+        Ok(Picker::new("History", items))
+    }
+
+    /// A picker of the saved queries that are for this database
+    fn saved_picker(&self, queries: &[SavedQuery]) -> Picker<Picked> {
+        let items = queries.iter()
+            .filter(|q| q.applies(self.conn.path()))
+            .map(|q| (format!("{}: {}", q.name, q.sql.replace('\n', " ")), Picked::Saved(q.clone())))
+            .collect();
+        Picker::new("Queries", items)
+    }
+
+    /// Open the result of a saved query as a tab named after it
+    pub fn run_saved(&mut self, run: &Run) -> Result<()> {
+        self.custom_seach(&run.sql())?;
+        let last = self.titles.len() - 1;
+        self.titles[last] = run.query.name.clone();
+        self.tab_last();
+        Ok(())
     }
 
+    /// Load the table and column names again, after the schema changed.
+    /// The sql that changed it already ran, so the old names are kept if they can't be read
+    pub fn refresh_completions(&mut self) {
+        if let Ok(completions) = Completions::load(&self.conn) {
+            self.completions = completions;
+        }
+    }
+
+    /// Load the rows of a tab, the sort, filter and cursor of the old rows are kept
     pub fn populate_table(&mut self, index: usize) -> Result<()> {
-        let table = &self.titles[index];
-        let query = format!("SELECT * FROM {}", table);
-        let (scheme, ents) = self.get_entries(&query)?;
-        self.tables[index] = Some(DbTable::new(query, scheme, ents));
+        let old = self.tables[index].take();
+        let sort = old.as_ref().map(|t| t.sort.clone()).unwrap_or_default();
+        let filter = old.as_ref().map(|t| t.filter.clone()).unwrap_or_default();
+        let res = self.load_table(index, &sort, &filter);
+        if res.is_err() {
+            self.tables[index] = old;
+            return res;
+        }
+        if let (Some(table), Some(old)) = (self.tables[index].as_mut(), old) {
+            table.col = old.col.min(table.hlen.saturating_sub(1));
+            table.hstate = old.hstate.min(table.col);
+            table.dirty = old.dirty;
+        }
         Ok(())
     }
 
+    fn load_table(&mut self, index: usize, sort: &Sort, filter: &Filter) -> Result<()> {
+        let order_by = sort.order_by();
+        let (table, view) = match self.kinds[index] {
+            TabKind::Table(ref table) => (table, false),
+            TabKind::View(ref table) => (table, true),
+            TabKind::Custom(ref query) => {
+                let filtered = filter.apply(query);
+                let mut dbtable = match self.query_table(&filtered, &order_by) {
+                    Err(_) if !filter.is_empty() => bail!("Can't filter the rows of this query"),
+                    res => res?,
+                };
+                dbtable.sort = sort.clone();
+                dbtable.filter = filter.clone();
+                self.tables[index] = Some(dbtable);
+                return Ok(());
+            }
+        };
+        let where_clause = filter.where_clause();
+        let query = format!("SELECT * FROM {}{}", table.sql(), where_clause);
+        // Fetch the rowid as the first column so rows can be edited,
+        // views and WITHOUT ROWID tables don't have one.
+        let rowid = match view {
+            true => None,
+            false => rowid_alias(&self.conn, table)?,
+        };
+        let pager = rowid.and_then(|rowid| {
+            let with_rowid = format!("SELECT {}, * FROM {}{}", rowid, table.sql(), where_clause);
+            Some((Pager::new(self.conn.clone(), &with_rowid, true).ok()?, rowid))
+        });
+        let mut dbtable = match pager {
+            Some((pager, rowid)) => DbTable::paged(query, pager.order_by(order_by)?)?.editable(table.clone(), rowid),
+            None => {
+                let pager = Pager::new(self.conn.clone(), &query, false)?;
+                DbTable::paged(query, pager.order_by(order_by)?)?
+            }
+        };
+        dbtable.sort = sort.clone();
+        dbtable.filter = filter.clone();
+        self.tables[index] = Some(dbtable);
+        Ok(())
+    }
+
+    fn query_table(&self, query: &str, order_by: &str) -> Result<DbTable> {
+        let table = match Pager::new(self.conn.clone(), query, false) {
+            Ok(pager) => DbTable::paged(query.to_owned(), pager.order_by(order_by.to_owned())?)?,
+            Err(_) if !order_by.is_empty() => bail!("Can't sort the rows of this query"),
+            // Statements like PRAGMA can't be paged, so load everything
+            Err(_) => {
+                let (scheme, ents) = self.get_entries(query)?;
+                DbTable::new(query.to_owned(), scheme, ents)
+            }
+        };
+        Ok(table)
+    }
+
     pub fn custom_seach(&mut self, query: &str) -> Result<()> {
-        let (scheme, ents) = self.get_entries(query)?;
-        self.tables.push(Some(DbTable::new(query.to_owned(), scheme, ents)));
+        let table = self.query_table(query, "")?;
+        self.tables.push(Some(table));
         self.titles.push("custom search".to_owned());
+        self.kinds.push(TabKind::Custom(query.to_owned()));
+        Ok(())
+    }
+
+    /// Remember the search and the cursor of the selected tab before searching it
+    pub fn start_search(&mut self) -> Result<()> {
+        let table = self.selected()?;
+        self.search_start = Some((table.grep.clone(), table.selected(), table.col));
+        Ok(())
+    }
+
+    /// Put back the search and the cursor of the selected tab from before searching
+    pub fn restore_search(&mut self) -> Result<()> {
+        let Some((grep, row, col)) = self.search_start.clone() else {
+            return Ok(());
+        };
+        let table = self.mutselected()?;
+        table.grep = grep;
+        table.goto((row.unwrap_or(0), col))?;
+        Ok(())
+    }
+
+    /// Search the selected tab and move to the first match from where the search started
+    pub fn search(&mut self, pattern: &str, opts: GrepOpts) -> Result<usize> {
+        self.restore_search()?;
+        let table = self.mutselected()?;
+        let found = table.grep(pattern, opts)?;
+        table.next_match()?;
+        Ok(found)
+    }
+
+    /// Search the loaded rows while the pattern is typed, enter searches all of them.
+    /// The old search is shown while it's empty or not a valid regex
+    fn preview_search(&mut self, input: &Option<Input>) -> Result<()> {
+        if let Some(Input { kind: InputType::Search(opts), input, .. }) = input {
+            self.restore_search()?;
+            let pattern = input.value();
+            if pattern.is_empty() {
+                return Ok(());
+            }
+            let table = self.mutselected()?;
+            if table.grep_loaded(pattern, *opts).is_ok() {
+                table.next_match()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one or more statements, tables they create can be completed after
+    pub fn exec(&mut self, sql: &str) -> Result<()> {
+        self.conn.execute_batch(sql)?;
+        self.refresh_completions();
+        Ok(())
+    }
+
+    /// Update a cell of the selected table, text is converted to the type of the old value
+    pub fn update(&mut self, row: usize, col: usize, text: &str) -> Result<()> {
+        let table = self.tables[self.index].as_ref().context("No table selected")?;
+        let i = table.loaded(row).context("Row isn't loaded")?;
+        let value = parse_value(text, &table.entries[i][col]);
+        self.update_value(row, col, value)
+    }
+
+    /// Set a cell of the selected table to value
+    pub fn update_value(&mut self, row: usize, col: usize, value: Value) -> Result<()> {
+        let conn = &self.conn;
+        let table = self.tables[self.index].as_mut().context("No table selected")?;
+        let (Some(name), Some(rowids)) = (&table.table, &table.rowids) else {
+            bail!("Can't edit rows without a rowid");
+        };
+        let i = table.loaded(row).context("Row isn't loaded")?;
+        let rowid = rowids[i];
+        let column = quote(&table.scheme[col]);
+
+        // Edits are staged in a transaction until they are committed
+        if conn.is_autocommit() {
+            conn.execute_batch("BEGIN")?;
+        }
+        let sql = format!("UPDATE {} SET {} = ? WHERE {} = ?", name.sql(), column, table.rowid);
+        conn.execute(&sql, rusqlite::params![value, rowid])?;
+        table.dirty.insert((rowid, col));
+
+        // Read the value back, the column affinity might have changed it
+        let sql = format!("SELECT {} FROM {} WHERE {} = ?", column, name.sql(), table.rowid);
+        table.entries[i][col] = conn.query_row(&sql, [rowid], |r| r.get(0))?;
         Ok(())
     }
 
-    pub fn exec(&self, sql: &str) -> Result<()> {
-        self.conn.execute(sql, [])?;
+    /// Set the cells in cols of the rows with rowids to value, in the open transaction
+    pub fn set_values(&mut self, rowids: &[i64], cols: &[usize], value: Value) -> Result<()> {
+        self.writable()?;
+        let conn = &self.conn;
+        let table = self.tables[self.index].as_mut().context("No table selected")?;
+        let name = table.table.as_ref().ok_or(EditError::NoRowid)?;
+        if conn.is_autocommit() {
+            conn.execute_batch("BEGIN")?;
+        }
+        let ids: Vec<String> = rowids.iter().map(|id| id.to_string()).collect();
+        for &col in cols {
+            let sql = format!("UPDATE {} SET {} = ? WHERE {} IN ({})", name.sql(), quote(&table.scheme[col]), table.rowid, ids.join(", "));
+            conn.execute(&sql, [&value])?;
+            table.dirty.extend(rowids.iter().map(|&id| (id, col)));
+        }
+        self.reload()
+    }
+
+    /// The keys of rows of the selected table, by rowid or by primary key for WITHOUT ROWID tables
+    fn row_keys(&mut self, rows: RangeInclusive<usize>) -> Result<Keys> {
+        let name = self.table_name()?;
+        let mut pk: Vec<_> = table_info(&self.conn, &name)?.into_iter().filter(|c| c.pk > 0).collect();
+        pk.sort_by_key(|c| c.pk);
+        let table = self.selected()?;
+        let (rowids, entries) = table.fetch(rows)?;
+        if !rowids.is_empty() {
+            return Ok(Keys::Rowids(table.rowid, rowids));
+        }
+        let columns: Vec<usize> = pk.iter()
+            .map(|c| table.scheme.iter().position(|name| *name == c.name))
+            .collect::<Option<_>>()
+            .filter(|columns: &Vec<usize>| !columns.is_empty())
+            .ok_or(EditError::NoRowid)?;
+        let keys = entries.iter().map(|row| columns.iter().map(|&i| row[i].clone()).collect()).collect();
+        Ok(Keys::Primary(pk.into_iter().map(|c| c.name).collect(), keys))
+    }
+
+    /// Ask before deleting rows of the selected table, telling what happens to rows that reference them
+    pub fn confirm_delete(&mut self, rows: RangeInclusive<usize>) -> Result<()> {
+        let name = self.table_name()?;
+        let keys = self.row_keys(rows)?;
+        let s = if keys.len() == 1 { "" } else { "s" };
+        let mut msg = format!("Delete {} row{} from {}?", keys.len(), s, name);
+        for reference in delete::references(&self.conn, &name, &keys)? {
+            msg.push_str(&format!("\n{}", reference));
+        }
+        self.confirm = Some(Confirm::new(msg, Pending::Delete(keys)));
+        self.mode = Mode::Confirm;
+        Ok(())
+    }
+
+    /// Delete rows from the selected table, in the open transaction
+    pub fn delete_rows(&mut self, keys: &Keys) -> Result<usize> {
+        let name = self.table_name()?;
+        if self.conn.is_autocommit() {
+            self.conn.execute_batch("BEGIN")?;
+        }
+        let deleted = delete::delete(&self.conn, &name, keys)?;
+        self.reload()?;
+        Ok(deleted)
+    }
+
+    /// The table of the selected tab, failing for views and queries
+    fn table_name(&self) -> Result<TableName> {
+        match self.kinds[self.index] {
+            TabKind::Table(ref name) => Ok(name.clone()),
+            TabKind::View(_) => bail!(EditError::ReadOnly),
+            TabKind::Custom(_) => bail!(EditError::NotTable),
+        }
+    }
+
+    /// Open a form to insert a row into the selected table, filled with the selected row if duplicate
+    pub fn insert_form(&mut self, duplicate: bool) -> Result<()> {
+        let name = self.table_name()?;
+        let mut form = Form::new(&self.conn, &name)?;
+        if duplicate {
+            let table = self.selected()?;
+            let row = table.selected_values().ok_or(EditError::NoSelection)?;
+            form.fill(&table.scheme, row);
+        }
+        self.form = Some(form);
+        Ok(())
+    }
+
+    /// Insert the row of the form in the open transaction, returning its rowid,
+    /// or its primary key for WITHOUT ROWID tables
+    pub fn insert(&mut self) -> Result<String> {
+        let form = self.form.as_ref().context("No row to insert")?;
+        if self.conn.is_autocommit() {
+            self.conn.execute_batch("BEGIN")?;
+        }
+        let rowid = form.insert(&self.conn)?;
+        let key = form.key();
+        self.form = None;
+        self.reload()?;
+        let Some(rowid) = rowid else {
+            return Ok(key);
+        };
+        let table = self.mutselected()?;
+        table.dirty.extend((0..table.hlen).map(|col| (rowid, col)));
+        Ok(rowid.to_string())
+    }
+
+    /// The cells of a visual selection of the selected tab
+    pub fn selected_rows(&mut self, visual: Selection) -> Result<Selected> {
+        let table = self.selected()?;
+        let (rows, cols) = table.range(visual).ok_or(EditError::NoSelection)?;
+        let (rowids, entries) = table.fetch(rows)?;
+        Ok(Selected {
+            columns: table.scheme[cols.clone()].to_vec(),
+            rowids,
+            rows: entries.iter().map(|row| row[cols.clone()].to_vec()).collect(),
+        })
+    }
+
+    /// Fail if the selected tab can't be changed
+    pub fn writable(&self) -> Result<()> {
+        if let TabKind::View(_) = self.kinds[self.index] {
+            bail!(EditError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        !self.conn.is_autocommit()
+    }
+
+    pub fn commit(&mut self) -> Result<()> {
+        if self.in_transaction() {
+            self.conn.execute_batch("COMMIT")?;
+        }
+        for table in self.tables.iter_mut().flatten() {
+            table.dirty.clear();
+        }
+        Ok(())
+    }
+
+    /// Throw away everything done in the transaction and reload the tables
+    pub fn rollback(&mut self) -> Result<()> {
+        if self.in_transaction() {
+            self.conn.execute_batch("ROLLBACK")?;
+        }
+        for table in self.tables.iter_mut().flatten() {
+            table.dirty.clear();
+        }
+        for i in 0..self.tables.len() {
+            let loaded = self.tables[i].as_ref().is_some_and(|t| t.table.is_some());
+            if loaded {
+                self.populate_table(i)?;
+            }
+        }
         Ok(())
     }
 
@@ -115,16 +537,119 @@ impl Qb {
         self.index = i;
     }
 
-    pub fn title_index(self, title: String) -> usize {
-        let mut i = 0;
-        for t in self.titles.iter() {
-            if *t == title {
-                break;
+    pub fn title_index(&self, title: &str) -> Option<usize> {
+        self.titles.iter().position(|t| t == title)
+    }
+
+    /// Select the tab for a table or view, adding a tab if it doesn't have one
+    pub fn open(&mut self, kind: TabKind) -> Result<()> {
+        let index = match self.kinds.iter().position(|k| *k == kind) {
+            Some(i) => i,
+            None => {
+                let title = match kind {
+                    TabKind::Table(ref name) | TabKind::View(ref name) => name.to_string(),
+                    TabKind::Custom(_) => "custom search".to_owned(),
+                };
+                self.titles.push(title);
+                self.kinds.push(kind);
+                self.tables.push(None);
+                self.titles.len() - 1
+            }
+        };
+        self.index = index;
+        self.selected()?;
+        Ok(())
+    }
+
+    /// The table name used in exported INSERT statements
+    fn export_name(&self) -> String {
+        match self.kinds[self.index] {
+            TabKind::Table(ref name) | TabKind::View(ref name) => name.name.clone(),
+            TabKind::Custom(_) => "query".to_owned(),
+        }
+    }
+
+    /// Export the rows of the selected tab, or only the visual selection
+    pub fn export<W: Write>(&mut self, format: Format, w: W, visual: Option<Selection>) -> Result<usize> {
+        let name = self.export_name();
+        if let Some(visual) = visual {
+            let selected = self.selected_rows(visual)?;
+            let mut exporter = Exporter::new(w, format, &name, &selected.columns)?;
+            for row in selected.rows.iter() {
+                exporter.row(row)?;
             }
-            i += 1;
+            return Ok(exporter.finish()?);
         }
-        i
+        let table = self.selected()?;
+        let mut exporter = Exporter::new(w, format, &name, &table.scheme)?;
+        let mut res = Ok(());
+        table.for_each(|_, row| {
+            if res.is_ok() {
+                res = exporter.row(row);
+            }
+        })?;
+        res?;
+        Ok(exporter.finish()?)
     }
+
+    /// Import a file into a table and open its tab, the table is named after the file if None
+    pub fn import(&mut self, path: &Path, table: Option<&str>) -> Result<import::Import> {
+        let table = match table {
+            Some(title) => match self.title_index(title).map(|i| &self.kinds[i]) {
+                Some(TabKind::Table(name)) => name.clone(),
+                Some(_) => bail!(EditError::ReadOnly),
+                None => TableName::new(None, title),
+            },
+            None => TableName::new(None, &alias(path)),
+        };
+        let res = import::import(&self.conn, path, &table)?;
+        self.refresh_completions();
+        self.open(TabKind::Table(table))?;
+        self.reload()?;
+        Ok(res)
+    }
+
+    pub fn load_schema(&mut self) -> Result<()> {
+        self.schema = Some(Schema::load(&self.conn)?);
+        Ok(())
+    }
+
+    /// Change the sort or filter of the selected tab and load its rows again,
+    /// both are put back if the rows can't be loaded
+    pub fn refine<F: FnOnce(&mut DbTable)>(&mut self, f: F) -> Result<()> {
+        let table = self.mutselected()?;
+        let (sort, filter) = (table.sort.clone(), table.filter.clone());
+        f(table);
+        let res = self.reload();
+        if res.is_err() {
+            let table = self.mutselected()?;
+            table.sort = sort;
+            table.filter = filter;
+        }
+        res
+    }
+
+    /// Copy the cell of the cursor to the clipboard
+    pub fn yank_cell(&mut self, command: Option<&str>) -> Result<()> {
+        let table = self.selected()?;
+        let (row, col) = table.selected_cell().ok_or(EditError::NoSelection)?;
+        let text = format::text(&table.entries[row - table.window][col]);
+        clipboard::copy(&text, command).context("Couldn't copy to the clipboard")?;
+        self.message = Some(format!("Yanked {} ({} chars)", table.scheme[col], text.chars().count()));
+        Ok(())
+    }
+
+    /// Copy rows of the selected tab to the clipboard in format
+    pub fn yank_rows(&mut self, format: Format, columns: &[String], rows: &[Vec<Value>], command: Option<&str>) -> Result<()> {
+        let name = self.export_name();
+        let text = clipboard::rows(format, &name, columns, rows)?;
+        clipboard::copy(&text, command).context("Couldn't copy to the clipboard")?;
+        let s = if rows.len() == 1 { "" } else { "s" };
+        let format = format!("{:?}", format).to_lowercase();
+        self.message = Some(format!("Yanked {} row{} as {}", rows.len(), s, format));
+        Ok(())
+    }
+
     pub fn reload(&mut self) -> Result<()> {
         self.populate_table(self.index)
     }
@@ -153,8 +678,121 @@ impl Qb {
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    db_path: PathBuf,
+    /// Databases to open, every database after the first is attached
+    #[clap(required = true)]
+    db_paths: Vec<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands that print to stdout instead of starting qb, for use in scripts
+#[derive(Subcommand)]
+enum Command {
+    /// List the tables and views
+    Tables {
+        #[clap(required = true)]
+        db_paths: Vec<PathBuf>,
+    },
+    /// Print the sql that creates the schema, or a table and its indexes and triggers
+    Schema {
+        db_path: PathBuf,
+        table: Option<String>,
+    },
+    /// Run a query and print the rows
+    Query {
+        db_path: PathBuf,
+        sql: String,
+        /// csv, tsv, json, ndjson, sql or table
+        #[clap(long, default_value = "table")]
+        format: Format,
+    },
+    /// Export a table
+    Export {
+        db_path: PathBuf,
+        table: String,
+        /// csv, tsv, json, ndjson, sql or table
+        #[clap(long, default_value = "csv")]
+        format: Format,
+        /// File to export to, stdout if not set
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Open the first database and attach the others
+fn open(paths: &[PathBuf]) -> Result<Qb> {
+    let conn = Connection::open(&paths[0]).context("Failed to connect to db")?;
+    let mut qb = Qb::new(conn)?;
+    for path in paths[1..].iter() {
+        let path_str = path.to_str().context("Database path isn't valid utf-8")?;
+        qb.attach(path_str, &alias(path))
+            .with_context(|| format!("Failed to attach {}", path.display()))?;
+    }
+    Ok(qb)
+}
+
+/// Run a command, errors are returned so qb exits with a failure
+fn run_command(command: Command) -> Result<()> {
+    match command {
+        Command::Tables { db_paths } => {
+            let qb = open(&db_paths)?;
+            for title in qb.titles.iter() {
+                println!("{}", title);
+            }
+        }
+        Command::Schema { db_path, table } => {
+            let schema = Schema::load(&Connection::open(db_path)?)?;
+            let objects: Vec<_> = schema.objects.iter()
+                .filter(|o| table.as_ref().is_none_or(|t| o.table.eq_ignore_ascii_case(t)))
+                .filter_map(|o| o.sql.as_ref())
+                .collect();
+            if let (Some(table), true) = (table.as_ref(), objects.is_empty()) {
+                bail!("No table named {}", table);
+            }
+            for sql in objects {
+                println!("{};", sql);
+            }
+        }
+        Command::Query { db_path, sql, format } => {
+            let qb = open(&[db_path])?;
+            let (scheme, entries) = qb.get_entries(&sql)?;
+            let mut exporter = Exporter::new(io::stdout().lock(), format, "query", &scheme)?;
+            for row in entries.iter() {
+                exporter.row(row)?;
+            }
+            exporter.finish()?;
+        }
+        Command::Export { db_path, table, format, output } => {
+            let mut qb = open(&[db_path])?;
+            let index = qb.title_index(&table).with_context(|| format!("No table named {}", table))?;
+            qb.set(index);
+            match output {
+                Some(path) => {
+                    let file = File::create(&path).with_context(|| format!("Couldn't create {}", path.display()))?;
+                    qb.export(format, BufWriter::new(file), None)?;
+                }
+                None => {
+                    qb.export(format, io::stdout().lock(), None)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Make a schema name from the file name of a database
+fn alias(path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let alias: String = stem.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    match alias.as_str() {
+        "" | "main" | "temp" => format!("db_{}", alias),
+        _ => alias,
+    }
 }
 
 pub fn startup() -> Result<Terminal<CrosstermBackend<Stdout>>> {
@@ -173,34 +811,48 @@ fn shutdown(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
 }
 
 fn parse_command(input: &str) -> Result<(InputType, String)> {
-    let mut parts = input.split_whitespace();
-
-    let kind = parts.next();
-    let Some(kind) = kind else {
+    let input = input.trim();
+    // the args are kept as they are, sql can have strings with spaces in them
+    let (kind, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    if kind.is_empty() {
         bail!("Missing kind");
-    };
+    }
     let kind = kind.try_into()?;
 
-    let args = parts.fold(String::new(), |a, b| a + b + "\n");
-
-    Ok((kind, args))
+    Ok((kind, args.trim_start().to_owned()))
 }
 
 
+/// Ask for the next parameter of a saved query, or run it once they all have a value
+fn ask_or_run<B: Backend>(qb: &mut Qb, run: Run, input: &mut Option<Input>, terminal: &mut Terminal<B>) -> Result<()> {
+    if run.next().is_none() {
+        return qb.run_saved(&run);
+    }
+    *input = Some(Input::new(InputType::Param(run), String::new()));
+    terminal.show_cursor()?;
+    qb.mode = Mode::Input;
+    Ok(())
+}
+
 /// Handle an event
 /// Returning a true means that we want to break the loop
 fn event<B: Backend>(qb: &mut Qb, cfg: &Config, input: &mut Option<Input>, last_err: &mut Option<anyhow::Error>, terminal: &mut Terminal<B>) -> Result<bool> {
     if event::poll(Duration::from_millis(250))? {
-        if let Event::Key(key) = event::read()? {
+        if let Event::Key(mut key) = event::read()? {
+            qb.message = None;
+            // The case of a char already tells if shift was pressed
+            if let KeyCode::Char(_) = key.code {
+                key.modifiers.remove(KeyModifiers::SHIFT);
+            }
             match qb.mode {
                 Mode::Main => {
                     if let Some(action) = cfg.main.get(&key) {
                         match action {
                             MainAction::Next => {
-                                qb.mutselected()?.next();
+                                qb.mutselected()?.next()?;
                             }
                             MainAction::Prev => {
-                                qb.mutselected()?.prev();
+                                qb.mutselected()?.prev()?;
                             }
                             MainAction::Hnext => {
                                 qb.mutselected()?.hnext();
@@ -208,11 +860,81 @@ fn event<B: Backend>(qb: &mut Qb, cfg: &Config, input: &mut Option<Input>, last_
                             MainAction::Hprev => {
                                 qb.mutselected()?.hprev();
                             }
+                            MainAction::Sort => {
+                                qb.refine(DbTable::sort_toggle)?;
+                            }
+                            MainAction::SortAdd => {
+                                qb.refine(DbTable::sort_add)?;
+                            }
+                            MainAction::Filter => {
+                                let table = qb.selected()?;
+                                let column = table.scheme.get(table.col).map(|c| format!("{} ", c)).unwrap_or_default();
+                                *input = Some(Input::new(InputType::Filter, column));
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Input;
+                                *last_err = None;
+                            }
+                            MainAction::Yank => {
+                                qb.yank_cell(cfg.clipboard.as_deref())?;
+                            }
+                            MainAction::YankRow(format) => {
+                                let format = format.as_str().try_into()?;
+                                let table = qb.selected()?;
+                                let row = table.selected_values().cloned().ok_or(EditError::NoSelection)?;
+                                let columns = table.scheme.clone();
+                                qb.yank_rows(format, &columns, &[row], cfg.clipboard.as_deref())?;
+                            }
+                            MainAction::Insert | MainAction::Duplicate => {
+                                qb.insert_form(matches!(action, MainAction::Duplicate))?;
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Form;
+                                *last_err = None;
+                            }
+                            MainAction::SavedQueries => {
+                                // a picker from main goes back to it
+                                *input = None;
+                                qb.picker = Some(qb.saved_picker(&cfg.queries));
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Picker;
+                                *last_err = None;
+                            }
+                            MainAction::Delete => {
+                                let row = qb.selected()?.selected().ok_or(EditError::NoSelection)?;
+                                qb.confirm_delete(row..=row)?;
+                            }
+                            MainAction::VisualLine | MainAction::VisualBlock => {
+                                let kind = match action {
+                                    MainAction::VisualLine => Kind::Line,
+                                    _ => Kind::Block,
+                                };
+                                let table = qb.mutselected()?;
+                                let cell = table.selected_cell().ok_or(EditError::NoSelection)?;
+                                table.visual = Some(Selection::new(kind, cell));
+                                qb.mode = Mode::Visual;
+                            }
+                            MainAction::FilterValue => {
+                                qb.refine(DbTable::filter_value)?;
+                            }
+                            MainAction::Exclude => {
+                                qb.refine(DbTable::exclude_value)?;
+                            }
+                            MainAction::Unfilter => {
+                                qb.refine(|t| { t.filter.conditions.pop(); })?;
+                            }
+                            MainAction::ClearFilters => {
+                                qb.refine(|t| t.filter = Filter::default())?;
+                            }
+                            MainAction::Wider => {
+                                qb.mutselected()?.resize(2);
+                            }
+                            MainAction::Narrower => {
+                                qb.mutselected()?.resize(-2);
+                            }
                             MainAction::First => {
-                                qb.mutselected()?.first();
+                                qb.mutselected()?.first()?;
                             }
                             MainAction::Last => {
-                                qb.mutselected()?.last();
+                                qb.mutselected()?.last()?;
                             }
                             MainAction::Tnext => {
                                 qb.next();
@@ -224,6 +946,8 @@ fn event<B: Backend>(qb: &mut Qb, cfg: &Config, input: &mut Option<Input>, last_
                                 qb.reload()?;
                             }
                             MainAction::Zoom => {
+                                let table = qb.mutselected()?;
+                                table.zoom.focus(table.col, table.hlen);
                                 qb.mode = Mode::Zoom;
                             }
                             MainAction::InputCurrent(pree) => {
@@ -241,15 +965,142 @@ fn event<B: Backend>(qb: &mut Qb, cfg: &Config, input: &mut Option<Input>, last_
                                 qb.mode = Mode::Input;
                                 *last_err = None;
                             }
+                            MainAction::Editor(kind) => {
+                                let kind: InputType = kind.as_str().try_into()?;
+                                let text = match kind {
+                                    InputType::Query => qb.selected()?.search.clone(),
+                                    _ => String::new(),
+                                };
+                                qb.editor = Some(Editor::new(kind, &text));
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Editor;
+                                *last_err = None;
+                            }
+                            MainAction::Edit => {
+                                qb.writable()?;
+                                let table = qb.selected()?;
+                                let (row, col) = table.edit_cell()?;
+                                let current = format::text(&table.entries[row - table.window][col]);
+                                *input = Some(Input::new(InputType::Edit(row, col), current));
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Input;
+                                *last_err = None;
+                            }
+                            MainAction::Commit => {
+                                qb.commit()?;
+                            }
+                            MainAction::Rollback => {
+                                qb.rollback()?;
+                            }
+                            MainAction::Schema => {
+                                qb.load_schema()?;
+                                qb.mode = Mode::Schema;
+                            }
                             MainAction::Quit => {
-                                return Ok(true);
+                                if !qb.in_transaction() {
+                                    return Ok(true);
+                                }
+                                let msg = "There are uncommitted changes, quit anyway?".to_owned();
+                                qb.confirm = Some(Confirm::new(msg, Pending::Quit));
+                                qb.mode = Mode::Confirm;
                             }
                             MainAction::Search => {
+                                let table = qb.selected()?;
+                                let (pattern, opts) = match table.grep {
+                                    Some(ref grep) => (grep.pattern.clone(), grep.opts),
+                                    None => (String::new(), GrepOpts::default()),
+                                };
+                                *input = Some(Input::new(InputType::Search(opts), pattern));
+                                qb.start_search()?;
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Input;
+                                *last_err = None;
+                            }
+                            MainAction::NextMatch => {
+                                qb.mutselected()?.next_match()?;
+                            }
+                            MainAction::PrevMatch => {
+                                qb.mutselected()?.prev_match()?;
                             }
                         }
                     }
                 }
                 Mode::Visual => {
+                    if let Some(action) = cfg.visual.get(&key) {
+                        match action {
+                            VisualAction::Back => {
+                                qb.mutselected()?.visual = None;
+                                qb.mode = Mode::Main;
+                            }
+                            VisualAction::Next => {
+                                qb.mutselected()?.next()?;
+                            }
+                            VisualAction::Prev => {
+                                qb.mutselected()?.prev()?;
+                            }
+                            VisualAction::Hnext => {
+                                qb.mutselected()?.hnext();
+                            }
+                            VisualAction::Hprev => {
+                                qb.mutselected()?.hprev();
+                            }
+                            VisualAction::First => {
+                                qb.mutselected()?.first()?;
+                            }
+                            VisualAction::Last => {
+                                qb.mutselected()?.last()?;
+                            }
+                            VisualAction::Line | VisualAction::Block => {
+                                let kind = match action {
+                                    VisualAction::Line => Kind::Line,
+                                    _ => Kind::Block,
+                                };
+                                let table = qb.mutselected()?;
+                                match table.visual {
+                                    Some(ref mut visual) if visual.kind != kind => visual.kind = kind,
+                                    _ => {
+                                        table.visual = None;
+                                        qb.mode = Mode::Main;
+                                    }
+                                }
+                            }
+                            VisualAction::Yank(format) => {
+                                let format = format.as_str().try_into()?;
+                                let visual = qb.mutselected()?.visual.take().ok_or(EditError::NoSelection)?;
+                                qb.mode = Mode::Main;
+                                let selected = qb.selected_rows(visual)?;
+                                qb.yank_rows(format, &selected.columns, &selected.rows, cfg.clipboard.as_deref())?;
+                            }
+                            VisualAction::Input(pree) => {
+                                *input = Some(Input::new(InputType::Exec, pree.clone()));
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Input;
+                                *last_err = None;
+                            }
+                            VisualAction::Delete => {
+                                let table = qb.mutselected()?;
+                                let visual = table.visual.take().ok_or(EditError::NoSelection)?;
+                                let (rows, _) = table.range(visual).ok_or(EditError::NoSelection)?;
+                                qb.mode = Mode::Main;
+                                qb.confirm_delete(rows)?;
+                            }
+                            VisualAction::Set => {
+                                let visual = qb.mutselected()?.visual.take().ok_or(EditError::NoSelection)?;
+                                qb.mode = Mode::Main;
+                                qb.writable()?;
+                                let rowids = qb.selected_rows(visual)?.rowids;
+                                if rowids.is_empty() {
+                                    bail!(EditError::NoRowid);
+                                }
+                                let table = qb.selected()?;
+                                let (_, cols) = table.range(visual).ok_or(EditError::NoSelection)?;
+                                *input = Some(Input::new(InputType::Set(rowids, cols.collect()), String::new()));
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Input;
+                                *last_err = None;
+                            }
+                        }
+                    }
                 }
                 Mode::Zoom => {
                     if let Some(action) = cfg.zoom.get(&key) {
@@ -273,52 +1124,452 @@ fn event<B: Backend>(qb: &mut Qb, cfg: &Config, input: &mut Option<Input>, last_
                                 let table = qb.mutselected()?;
                                 table.zoom.prev();
                             }
+                            ZoomAction::Blob => {
+                                let table = qb.selected()?;
+                                let (Some(row), Some(values)) = (table.selected(), table.selected_values()) else {
+                                    bail!(EditError::NoSelection);
+                                };
+                                // the first blob from the first column shown, or a NULL to load a file into
+                                let start = table.zoom.column();
+                                let col = (start..values.len()).find(|&i| matches!(values[i], Value::Blob(_)))
+                                    .or_else(|| (start..values.len()).find(|&i| values[i] == Value::Null))
+                                    .context("No blob in the row")?;
+                                let blob = BlobView::new(&values[col], (row, col)).context("No blob in the row")?;
+                                qb.blob = Some(blob);
+                                qb.mode = Mode::Blob;
+                            }
+                        }
+                    }
+                }
+                Mode::Blob => {
+                    if let (Some(action), Some(blob)) = (cfg.blob.get(&key), qb.blob.as_mut()) {
+                        match action {
+                            BlobAction::Back => {
+                                qb.blob = None;
+                                qb.mode = Mode::Zoom;
+                            }
+                            BlobAction::Down => blob.down(1),
+                            BlobAction::Up => blob.up(1),
+                            BlobAction::PageDown => blob.page_down(),
+                            BlobAction::PageUp => blob.page_up(),
+                            BlobAction::First => blob.first(),
+                            BlobAction::Last => blob.last(),
+                            BlobAction::Save | BlobAction::Load => {
+                                let kind = match action {
+                                    BlobAction::Save => InputType::Save,
+                                    _ => InputType::Load,
+                                };
+                                *input = Some(Input::new(kind, String::new()));
+                                terminal.show_cursor()?;
+                                qb.mode = Mode::Input;
+                                *last_err = None;
+                            }
+                        }
+                    }
+                }
+                Mode::Form => {
+                    if let Some(action) = cfg.form.get(&key) {
+                        match action {
+                            FormAction::Back => {
+                                qb.form = None;
+                                terminal.hide_cursor()?;
+                                qb.mode = Mode::Main;
+                            }
+                            FormAction::Next => qb.form.as_mut().context("No row to insert")?.next(),
+                            FormAction::Prev => qb.form.as_mut().context("No row to insert")?.prev(),
+                            FormAction::Submit => {
+                                let rowid = qb.insert()?;
+                                terminal.hide_cursor()?;
+                                qb.mode = Mode::Main;
+                                *last_err = None;
+                                qb.message = Some(format!("Inserted row {}, w to commit", rowid));
+                            }
+                        }
+                    } else if let Some(ref mut form) = qb.form {
+                        match (cfg.input.get(&key), key.code) {
+                            (Some(action), _) => {
+                                if let Ok(req) = action.try_into() {
+                                    form.handle(req);
+                                }
+                            }
+                            (None, KeyCode::Char(char)) => form.handle(tui_input::InputRequest::InsertChar(char)),
+                            _ => {}
+                        }
+                    }
+                }
+                Mode::Editor => {
+                    let editor = qb.editor.as_mut().context("No sql to edit")?;
+                    match (cfg.editor.get(&key), key.code) {
+                        (Some(EditorAction::Submit), _) => {
+                            let sql = editor.text();
+                            let kind = editor.kind.to_string();
+                            // the editor stays open when the sql fails, to fix it
+                            let res = match editor.kind {
+                                InputType::Query => qb.custom_seach(&sql).map(|_| qb.tab_last()),
+                                _ => qb.exec(&sql),
+                            };
+                            qb.remember(&kind, &sql, res)?;
+                            qb.editor = None;
+                            terminal.hide_cursor()?;
+                            qb.mode = Mode::Main;
+                            *last_err = None;
+                        }
+                        (Some(EditorAction::HistorySearch), _) => {
+                            qb.picker = Some(qb.history_picker()?);
+                            qb.mode = Mode::Picker;
+                        }
+                        (Some(EditorAction::Leave), _) if editor.completion.is_some() => {
+                            editor.completion = None;
+                        }
+                        (Some(EditorAction::Leave), _) => {
+                            qb.editor = None;
+                            terminal.hide_cursor()?;
+                            qb.mode = Mode::Main;
+                            *last_err = None;
+                        }
+                        (Some(EditorAction::Complete), _) if editor.completion.is_some() => editor.complete(),
+                        (Some(EditorAction::NextCompletion), _) => {
+                            if let Some(ref mut completion) = editor.completion {
+                                completion.next();
+                            }
+                        }
+                        (Some(EditorAction::PrevCompletion), _) => {
+                            if let Some(ref mut completion) = editor.completion {
+                                completion.prev();
+                            }
+                        }
+                        (Some(EditorAction::DeletePrevChar), _) => {
+                            editor.backspace();
+                            editor.update_completion(&qb.completions);
+                        }
+                        (None, KeyCode::Char(char)) => {
+                            editor.insert(char);
+                            editor.update_completion(&qb.completions);
+                        }
+                        (action, _) => {
+                            editor.completion = None;
+                            match action {
+                                Some(EditorAction::Newline) => editor.newline(),
+                                Some(EditorAction::Complete | EditorAction::Indent) => editor.indent(),
+                                Some(EditorAction::Unindent) => editor.unindent(),
+                                Some(EditorAction::Left) => editor.left(),
+                                Some(EditorAction::Right) => editor.right(),
+                                Some(EditorAction::Up) if editor.cursor().0 == 0 => editor.history_prev(&qb.history),
+                                Some(EditorAction::Down) if editor.on_last_line() => editor.history_next(&qb.history),
+                                Some(EditorAction::Up) => editor.up(),
+                                Some(EditorAction::Down) => editor.down(),
+                                Some(EditorAction::Home) => editor.home(),
+                                Some(EditorAction::End) => editor.end(),
+                                Some(EditorAction::DeleteNextChar) => editor.delete(),
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                Mode::Picker => {
+                    // the history is searched from the editor or the input line, saved queries are picked from main
+                    let back = match (&qb.editor, &input) {
+                        (Some(_), _) => Mode::Editor,
+                        (None, Some(_)) => Mode::Input,
+                        (None, None) => Mode::Main,
+                    };
+                    if back == Mode::Main && matches!(cfg.picker.get(&key), Some(PickerAction::Back | PickerAction::Pick)) {
+                        terminal.hide_cursor()?;
+                    }
+                    if let Some(action) = cfg.picker.get(&key) {
+                        match action {
+                            PickerAction::Back => {
+                                qb.picker = None;
+                                qb.mode = back;
+                            }
+                            PickerAction::Next => qb.picker.as_mut().context("Nothing to pick")?.next(),
+                            PickerAction::Prev => qb.picker.as_mut().context("Nothing to pick")?.prev(),
+                            PickerAction::Pick => {
+                                let picker = qb.picker.take().context("Nothing to pick")?;
+                                qb.mode = back;
+                                match (picker.selected(), qb.editor.as_mut(), input.as_mut()) {
+                                    (Some(Picked::History(entry)), Some(editor), _) => editor.set(&entry.kind, &entry.sql),
+                                    (Some(Picked::History(entry)), None, Some(_)) if entry.is_multiline() => {
+                                        // sql on several lines is opened in the editor
+                                        let mut editor = Editor::new(InputType::Exec, "");
+                                        editor.set(&entry.kind, &entry.sql);
+                                        qb.editor = Some(editor);
+                                        *input = None;
+                                        qb.mode = Mode::Editor;
+                                    }
+                                    (Some(Picked::History(entry)), None, Some(input)) => input.input = tui_input::Input::new(entry.command()),
+                                    (Some(Picked::Saved(query)), _, _) => ask_or_run(qb, Run::new(query.clone()), input, terminal)?,
+                                    _ => {}
+                                }
+                            }
+                        }
+                    } else if let Some(ref mut picker) = qb.picker {
+                        match (cfg.input.get(&key), key.code) {
+                            (Some(action), _) => {
+                                if let Ok(req) = action.try_into() {
+                                    picker.handle(req);
+                                }
+                            }
+                            (None, KeyCode::Char(char)) => picker.handle(tui_input::InputRequest::InsertChar(char)),
+                            _ => {}
+                        }
+                    }
+                }
+                Mode::Schema => {
+                    if let (Some(action), Some(schema)) = (cfg.schema.get(&key), qb.schema.as_mut()) {
+                        match action {
+                            SchemaAction::Back => {
+                                qb.mode = Mode::Main;
+                            }
+                            SchemaAction::Next => schema.next(),
+                            SchemaAction::Prev => schema.prev(),
+                            SchemaAction::Expand => schema.expand(),
+                            SchemaAction::Collapse => schema.collapse(),
+                            SchemaAction::Toggle => schema.toggle(),
+                            SchemaAction::Open => {
+                                let kind = match schema.selected() {
+                                    Some(obj) if obj.kind == "view" => TabKind::View(obj.table_name()),
+                                    Some(obj) if obj.is_openable() => TabKind::Table(obj.table_name()),
+                                    _ => return Ok(false),
+                                };
+                                qb.mode = Mode::Main;
+                                qb.open(kind)?;
+                            }
+                        }
+                    }
+                }
+                Mode::Confirm => {
+                    if let Some(action) = cfg.confirm.get(&key) {
+                        qb.mode = Mode::Main;
+                        let confirm = qb.confirm.take();
+                        if let (ConfirmAction::Yes, Some(confirm)) = (action, confirm) {
+                            match confirm.action {
+                                Pending::Quit => {
+                                    return Ok(true);
+                                }
+                                Pending::Delete(keys) => {
+                                    let deleted = qb.delete_rows(&keys)?;
+                                    let s = if deleted == 1 { "" } else { "s" };
+                                    qb.message = Some(format!("Deleted {} row{}, w to commit", deleted, s));
+                                }
+                            }
                         }
                     }
                 }
                 Mode::Input => {
                     if let Some(action) = cfg.input.get(&key) {
                         match action {
+                            InputAction::Leave if input.as_ref().is_some_and(|i| i.completion.is_some()) => {
+                                if let Some(ref mut input) = input {
+                                    input.completion = None;
+                                }
+                            }
                             InputAction::Leave => {
-                                qb.mode = Mode::Main;
+                                if let Some(Input { kind: InputType::Search(_), .. }) = input {
+                                    qb.restore_search()?;
+                                    qb.search_start = None;
+                                }
+                                let visual = qb.selected().is_ok_and(|t| t.visual.is_some());
+                                qb.mode = match input {
+                                    Some(Input { kind: InputType::Save | InputType::Load, .. }) => Mode::Blob,
+                                    _ if visual => Mode::Visual,
+                                    _ => Mode::Main,
+                                };
                             }
                             InputAction::Enter => {
-                                if let Some(ref inner) = input { 
-                                    let res = parse_command(inner.input.value());
-                                    *input = None;
+                                if let Some(inner) = input.take() { 
                                     qb.mode = Mode::Main;
                                     terminal.hide_cursor()?;
-                                    let (kind, args) = res?;
+                                    // a command from visual mode works on the selection, which ends with it
+                                    let visual = qb.mutselected()?.visual.take();
+
+                                    // edits and searches aren't commands, the whole line is the value
+                                    match inner.kind {
+                                        InputType::Edit(row, col) => {
+                                            return qb.update(row, col, inner.input.value()).map(|_| false);
+                                        }
+                                        InputType::Save => {
+                                            qb.mode = Mode::Blob;
+                                            let path = inner.input.value();
+                                            let blob = qb.blob.as_ref().context("No blob to save")?;
+                                            std::fs::write(path, &blob.data)
+                                                .with_context(|| format!("Couldn't write {}", path))?;
+                                            qb.message = Some(format!("Saved {} bytes to {}", blob.data.len(), path));
+                                            return Ok(false);
+                                        }
+                                        InputType::Load => {
+                                            qb.mode = Mode::Blob;
+                                            qb.writable()?;
+                                            let path = inner.input.value();
+                                            let data = std::fs::read(path)
+                                                .with_context(|| format!("Couldn't read {}", path))?;
+                                            let (row, col) = qb.blob.as_ref().context("No blob to load into")?.cell;
+                                            qb.update_value(row, col, Value::Blob(data.clone()))?;
+                                            qb.message = Some(format!("Loaded {} bytes from {}", data.len(), path));
+                                            if let Some(ref mut blob) = qb.blob {
+                                                blob.data = data;
+                                                blob.first();
+                                            }
+                                            return Ok(false);
+                                        }
+                                        InputType::Param(mut run) => {
+                                            run.set(filter::parse_value(inner.input.value()));
+                                            return ask_or_run(qb, run, input, terminal).map(|_| false);
+                                        }
+                                        InputType::Set(rowids, cols) => {
+                                            // numbers are set as numbers, \N is NULL like when editing a cell
+                                            let value = match inner.input.value() {
+                                                "\\N" => Value::Null,
+                                                text => filter::parse_value(text),
+                                            };
+                                            qb.set_values(&rowids, &cols, value)?;
+                                            let s = if rowids.len() == 1 { "" } else { "s" };
+                                            qb.message = Some(format!("Set {} row{}, w to commit", rowids.len(), s));
+                                            return Ok(false);
+                                        }
+                                        InputType::Filter => {
+                                            let condition = Condition::parse(inner.input.value(), &qb.selected()?.scheme)?;
+                                            return qb.refine(|t| t.filter.add(condition)).map(|_| false);
+                                        }
+                                        InputType::Search(opts) => {
+                                            // the matches of the pattern are already shown, unless it's not valid
+                                            let pattern = inner.input.value();
+                                            let found = qb.search(pattern, opts);
+                                            qb.search_start = None;
+                                            if found? == 0 {
+                                                bail!("Pattern not found: {}", pattern);
+                                            }
+                                            return Ok(false);
+                                        }
+                                        _ => {}
+                                    }
+                                    let (kind, args) = parse_command(inner.input.value())?;
 
                                     match kind {
                                         InputType::Exec => {
-                                            qb.exec(&args)?;
+                                            let res = qb.exec(&args);
+                                            qb.remember("exec", &args, res)?;
                                         }
                                         InputType::Query => {
-                                            let res = qb.custom_seach(&args);
-                                            if res.is_ok() {
-                                                qb.tab_last()
+                                            let res = qb.custom_seach(&args).map(|_| qb.tab_last());
+                                            qb.remember("query", &args, res)?;
+                                        }
+                                        InputType::Export => {
+                                            // export <format> <path>
+                                            let args: Vec<&str> = args.split_whitespace().collect();
+                                            let [format, path] = args[..] else {
+                                                bail!("Usage: export <csv|tsv|json|ndjson|sql|table> <path>");
+                                            };
+                                            let format = format.try_into()?;
+                                            let file = File::create(path)
+                                                .with_context(|| format!("Couldn't create {}", path))?;
+                                            let rows = qb.export(format, BufWriter::new(file), visual)?;
+                                            qb.message = Some(format!("Exported {} rows to {}", rows, path));
+                                        }
+                                        InputType::Import => {
+                                            // import <path> [table]
+                                            let args: Vec<&str> = args.split_whitespace().collect();
+                                            let (path, table) = match args[..] {
+                                                [path] => (path, None),
+                                                [path, table] => (path, Some(table)),
+                                                _ => bail!("Usage: import <path> [table]"),
+                                            };
+                                            let res = qb.import(Path::new(path), table)?;
+                                            let title = &qb.titles[qb.index];
+                                            if let Some(err) = res.errors.first() {
+                                                bail!("Imported {} rows into {}, {} failed, {}", res.rows, title, res.errors.len(), err);
                                             }
-                                            res?;
+                                            qb.message = Some(format!("Imported {} rows into {}", res.rows, title));
+                                        }
+                                        InputType::Attach => {
+                                            // attach <path> [as <alias>]
+                                            let args: Vec<&str> = args.split_whitespace().collect();
+                                            let (path, alias) = match args[..] {
+                                                [path] => (path, alias(Path::new(path))),
+                                                [path, "as", alias] => (path, alias.to_owned()),
+                                                _ => bail!("Usage: attach <path> [as <alias>]"),
+                                            };
+                                            qb.attach(path, &alias)?;
+                                        }
+                                        kind => {
+                                            bail!(ConvertError::InputTypeError(kind.to_string()));
                                         }
                                     }
                                 }
                             }
+                            InputAction::ToggleCase => {
+                                if let Some(Input { kind: InputType::Search(ref mut opts), .. }) = input {
+                                    opts.ignore_case = !opts.ignore_case;
+                                }
+                                qb.preview_search(input)?;
+                            }
+                            InputAction::ToggleRegex => {
+                                if let Some(Input { kind: InputType::Search(ref mut opts), .. }) = input {
+                                    opts.regex = !opts.regex;
+                                }
+                                qb.preview_search(input)?;
+                            }
+                            InputAction::Complete => {
+                                if let Some(ref mut input) = input {
+                                    input.complete();
+                                }
+                            }
+                            InputAction::HistoryPrev => {
+                                if let Some(ref mut input) = input {
+                                    input.completion = None;
+                                    input.history_prev(&qb.history);
+                                }
+                            }
+                            InputAction::HistoryNext => {
+                                if let Some(ref mut input) = input {
+                                    input.completion = None;
+                                    input.history_next(&qb.history);
+                                }
+                            }
+                            InputAction::HistorySearch => {
+                                if let Some(Input { kind: InputType::Exec | InputType::Query, .. }) = input {
+                                    qb.picker = Some(qb.history_picker()?);
+                                    qb.mode = Mode::Picker;
+                                }
+                            }
+                            InputAction::NextCompletion => {
+                                if let Some(Input { completion: Some(ref mut completion), .. }) = input {
+                                    completion.next();
+                                }
+                            }
+                            InputAction::PrevCompletion => {
+                                if let Some(Input { completion: Some(ref mut completion), .. }) = input {
+                                    completion.prev();
+                                }
+                            }
                             action => {
                                 if let Some(ref mut input) = input { 
                                     let req = action.try_into()?;
                                     input.handle(req);
+                                    // only deleting keeps completing the word
+                                    match action {
+                                        InputAction::DeletePrevChar => input.update_completion(&qb.completions),
+                                        _ => input.completion = None,
+                                    }
                                 }
+                                qb.preview_search(input)?;
                             }
                         }
-                    } else if let Some(ref mut input) = input { 
-                        if let KeyCode::Char(char) = key.code {
-                            input.handle(tui_input::InputRequest::InsertChar(char));
+                    } else {
+                        if let Some(ref mut input) = input {
+                            if let KeyCode::Char(char) = key.code {
+                                input.handle(tui_input::InputRequest::InsertChar(char));
+                                input.update_completion(&qb.completions);
+                            }
                         }
+                        qb.preview_search(input)?;
                     }
                 }
             }
         }
+    } else if let Ok(table) = qb.mutselected() {
+        // count the rows while no key is pressed, a failed count shows as ?
+        table.count().ok();
     }
     Ok(false)
 }
@@ -326,31 +1577,97 @@ fn event<B: Backend>(qb: &mut Qb, cfg: &Config, input: &mut Option<Input>, last_
 fn run_app<B: Backend>(mut qb: Qb, cfg: Config, terminal: &mut Terminal<B>) -> Result<()> {
     let mut input: Option<Input> = None;
     let mut last_err: Option<anyhow::Error> = None;
+    let theme = cfg.colors.theme()?;
     'lp: loop {
         let mode = qb.mode;
+        let message = qb.message.clone();
         terminal.draw(|f| {
             let rect = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(1)].as_ref())
                 .split(f.size());
-            ui::make_tabs(&qb, f, rect[0]);
+            ui::make_tabs(&qb, f, rect[0], &theme);
+            if let (Mode::Schema, Some(schema)) = (mode, qb.schema.as_mut()) {
+                schema.render(f, rect[1], &theme);
+                return;
+            }
             let table = qb.mutselected().expect("Couldn't select table");
-            table.render(f, rect[1]);
+            let area = match table.filter.is_empty() {
+                true => rect[1],
+                false => {
+                    let parts = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Length(1), Constraint::Min(0)].as_ref())
+                        .split(rect[1]);
+                    ui::chips(&table.filter, f, parts[0], &theme);
+                    parts[1]
+                }
+            };
+            table.render(f, area, &theme);
             match mode {
-                Mode::Main => {
+                Mode::Main | Mode::Visual => {
                     if let Some(ref err) = last_err {
-                        ui::input_err(&err.to_string(), f, rect[2])
+                        ui::input_err(&err.to_string(), f, rect[2], &theme)
+                    } else if let Some(ref msg) = message {
+                        ui::status(msg, f, rect[2])
+                    } else {
+                        ui::status(&table.status(), f, rect[2])
                     }
                 }
-                Mode::Visual => {
-                    // higligt the selected fields in grey?
-                }
                 Mode::Zoom => {
-                    table.zoom.render(table, f)
+                    table.zoom.render(table, f, &theme);
+                    if let Some(ref err) = last_err {
+                        ui::input_err(&err.to_string(), f, rect[2], &theme)
+                    }
+                }
+                Mode::Blob => {
+                    table.zoom.render(table, f, &theme);
+                    if let Some(ref mut blob) = qb.blob {
+                        blob.render(f, &theme)
+                    }
+                    if let Some(ref msg) = message {
+                        ui::status(msg, f, rect[2])
+                    } else if let Some(ref err) = last_err {
+                        ui::input_err(&err.to_string(), f, rect[2], &theme)
+                    }
+                }
+                Mode::Form => {
+                    // the form sets the cursor after the error does
+                    if let Some(ref err) = last_err {
+                        ui::input_err(&err.to_string(), f, rect[2], &theme)
+                    }
+                    if let Some(ref mut form) = qb.form {
+                        form.render(f, &theme)
+                    }
                 }
+                Mode::Editor => {
+                    // the editor sets the cursor after the error does
+                    if let Some(ref err) = last_err {
+                        ui::input_err(&err.to_string(), f, rect[2], &theme)
+                    }
+                    if let Some(ref mut editor) = qb.editor {
+                        editor.render(f, &theme)
+                    }
+                }
+                Mode::Picker => {
+                    if let Some(ref mut editor) = qb.editor {
+                        editor.render(f, &theme)
+                    } else if let Some(ref input) = input {
+                        input.render(f, rect[2], &theme)
+                    }
+                    if let Some(ref mut picker) = qb.picker {
+                        picker.render(f, &theme)
+                    }
+                }
+                Mode::Confirm => {
+                    if let Some(ref confirm) = qb.confirm {
+                        confirm.render(f, &theme)
+                    }
+                }
+                Mode::Schema => {}
                 Mode::Input => {
                     if let Some(ref input) = input {
-                        input.render(f, rect[2])
+                        input.render(f, rect[2], &theme)
                     }
                 }
             }
@@ -371,14 +1688,26 @@ fn run_app<B: Backend>(mut qb: Qb, cfg: Config, terminal: &mut Terminal<B>) -> R
 
 fn main() -> Result<()> {
     let args = Cli::parse();
+    if let Some(command) = args.command {
+        return run_command(command);
+    }
+
     let cfg: Config = confy::load("qb", None)
         .context("Couldn't load config file, remove it to get a new one")?;
+    // saved queries are opened as tabs named after them
+    if let Some(query) = cfg.queries.iter().find(|q| q.name.trim().is_empty()) {
+        bail!("The saved query {:?} needs a name", query.sql);
+    }
     confy::store("qb", None, &cfg).context("Couldn't update config")?;
 
-    let path = &args.db_path;
-    let conn = Connection::open(path).context("Failed to connect to db")?;
-    let qb = Qb::new(conn)?;
- 
+    let mut qb = open(&args.db_paths)?;
+    // the history of every database is kept next to the config file
+    let config = confy::get_configuration_file_path("qb", None).context("Couldn't find the config dir")?;
+    let database = qb.conn.path().filter(|p| !p.is_empty()).map(str::to_owned);
+    if let (Some(dir), Some(database)) = (config.parent(), database) {
+        qb.history = History::load(dir.join("history.ndjson"), &database, cfg.history_size).context("Couldn't read the history")?;
+    }
+
     let mut terminal = startup()?;
 
     terminal.clear().context("Clear error")?;
@@ -390,3 +1719,44 @@ fn main() -> Result<()> {
     res?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use qb::theme::Theme;
+    use ratatui::{backend::TestBackend, Terminal};
+    use rusqlite::Connection;
+
+    use super::{open, ui, Qb};
+
+    #[test]
+    fn test_unicode_tabs() {
+        let dir = std::env::temp_dir().join(format!("qb-tabs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (main, other) = (dir.join("ü.db"), dir.join("ärger.db"));
+        Connection::open(&main).unwrap().execute_batch("CREATE TABLE änderungen(a)").unwrap();
+        Connection::open(&other).unwrap().execute_batch("CREATE TABLE t(a)").unwrap();
+        let qb = open(&[main, other]).unwrap();
+        assert_eq!(qb.titles, ["änderungen", "ärger.t"]);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 3)).unwrap();
+        terminal.draw(|f| ui::make_tabs(&qb, f, f.size(), &Theme::default())).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_unicode_import() {
+        let dir = std::env::temp_dir().join(format!("qb-import-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("ä.csv");
+        fs::write(&csv, "a,b\n1,x\n").unwrap();
+        let mut qb = Qb::new(Connection::open_in_memory().unwrap()).unwrap();
+        assert_eq!(qb.import(&csv, None).unwrap().rows, 1);
+        assert_eq!(qb.titles[qb.index], "ä");
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 3)).unwrap();
+        terminal.draw(|f| ui::make_tabs(&qb, f, f.size(), &Theme::default())).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use std::rc::Rc;
+
+use rusqlite::{Connection, types::Value};
+
+/// Number of rows fetched at a time
+pub const PAGE: usize = 200;
+
+/// Fetches the rows of a query a page at a time
+#[derive(Clone)]
+pub struct Pager {
+    conn: Rc<Connection>,
+    query: String,
+    /// The first column of the query is the rowid
+    rowid: bool,
+    /// An ORDER BY clause for the rows of the query
+    order: String,
+}
+
+impl Pager {
+    /// Create a pager for query, fails if query can't be used as a subquery
+    pub fn new(conn: Rc<Connection>, query: &str, rowid: bool) -> rusqlite::Result<Self> {
+        let query = query.trim().trim_end_matches(';').to_owned();
+        // check that the query can be paged
+        conn.prepare(&format!("SELECT * FROM ({}) LIMIT 0", query))?;
+        Ok(Pager {
+            conn,
+            query,
+            rowid,
+            order: String::new(),
+        })
+    }
+
+    /// Order the rows, order_by is a whole ORDER BY clause on the columns of the query
+    pub fn order_by(mut self, order_by: String) -> rusqlite::Result<Self> {
+        self.conn.prepare(&format!("SELECT * FROM ({}){} LIMIT 0", self.query, order_by))?;
+        self.order = order_by;
+        Ok(self)
+    }
+
+    /// The query with its ORDER BY clause
+    fn ordered(&self) -> String {
+        match self.order.as_str() {
+            "" => self.query.clone(),
+            order => format!("SELECT * FROM ({}){}", self.query, order),
+        }
+    }
+
+    pub fn rowid(&self) -> bool {
+        self.rowid
+    }
+
+    pub fn columns(&self) -> rusqlite::Result<Vec<String>> {
+        let stmt = self.conn.prepare(&self.query)?;
+        let skip = usize::from(self.rowid);
+        Ok(stmt.column_names().iter().skip(skip).map(|s| s.to_string()).collect())
+    }
+
+    /// Fetch up to limit rows starting at offset, returning the rowids and the rows
+    pub fn fetch(&self, offset: usize, limit: usize) -> rusqlite::Result<(Vec<i64>, Vec<Vec<Value>>)> {
+        let sql = format!("SELECT * FROM ({}){} LIMIT ? OFFSET ?", self.query, self.order);
+        let mut stmt = self.conn.prepare(&sql)?;
+        let ncols = stmt.column_count();
+        let mut rows = stmt.query([limit as i64, offset as i64])?;
+
+        let mut rowids = Vec::new();
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut start = 0;
+            if self.rowid {
+                rowids.push(row.get(0)?);
+                start = 1;
+            }
+            let mut cols = Vec::with_capacity(ncols);
+            for i in start..ncols {
+                cols.push(row.get(i)?);
+            }
+            entries.push(cols);
+        }
+        Ok((rowids, entries))
+    }
+
+    pub fn count(&self) -> rusqlite::Result<usize> {
+        let sql = format!("SELECT COUNT(*) FROM ({})", self.query);
+        let count: i64 = self.conn.query_row(&sql, [], |r| r.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Go through all rows without keeping them in memory
+    pub fn for_each<F>(&self, mut f: F) -> rusqlite::Result<()>
+    where
+        F: FnMut(usize, &[Value]) {
+        let mut stmt = self.conn.prepare(&self.ordered())?;
+        let ncols = stmt.column_count();
+        let start = usize::from(self.rowid);
+        let mut rows = stmt.query([])?;
+        let mut i = 0;
+        while let Some(row) = rows.next()? {
+            let mut cols = Vec::with_capacity(ncols);
+            for j in start..ncols {
+                cols.push(row.get(j)?);
+            }
+            f(i, &cols);
+            i += 1;
+        }
+        Ok(())
+    }
+}
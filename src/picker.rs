@@ -0,0 +1,111 @@
+use ratatui::{prelude::{Backend, Constraint, Direction, Layout}, Frame, widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph}};
+use tui_input::{Input, InputRequest};
+
+use super::popup::centered_rect;
+use super::theme::Theme;
+
+/// A popup list to pick an item from, narrowed down by typing
+pub struct Picker<T> {
+    pub title: String,
+    /// The text shown and matched, and the item
+    items: Vec<(String, T)>,
+    pub filter: Input,
+    /// The items that contain every word of the filter
+    matches: Vec<usize>,
+    state: ListState,
+}
+
+impl<T> Picker<T> {
+    pub fn new(title: &str, items: Vec<(String, T)>) -> Self {
+        let mut picker = Picker {
+            title: title.to_owned(),
+            items,
+            filter: Input::default(),
+            matches: Vec::new(),
+            state: ListState::default(),
+        };
+        picker.update();
+        picker
+    }
+
+    fn update(&mut self) {
+        let words: Vec<String> = self.filter.value().split_whitespace().map(|w| w.to_lowercase()).collect();
+        self.matches = self.items.iter().enumerate()
+            .filter(|(_, (text, _))| {
+                let text = text.to_lowercase();
+                words.iter().all(|w| text.contains(w.as_str()))
+            })
+            .map(|(i, _)| i)
+            .collect();
+        self.state.select(if self.matches.is_empty() { None } else { Some(0) });
+    }
+
+    /// Edit the filter
+    pub fn handle(&mut self, req: InputRequest) {
+        if self.filter.handle(req).is_some_and(|r| r.value) {
+            self.update();
+        }
+    }
+
+    pub fn next(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some((i + 1).min(self.matches.len() - 1)));
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        let i = self.matches.get(self.state.selected()?)?;
+        Some(&self.items[*i].1)
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, theme: &Theme) {
+        let area = centered_rect(80, 60, f.size());
+        let block = Block::default()
+            .title(format!("{} ({}/{})", self.title, self.matches.len(), self.items.len()))
+            .title_style(theme.header)
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(inner);
+
+        let texts = &self.items;
+        let items: Vec<ListItem> = self.matches.iter().map(|i| ListItem::new(texts[*i].0.as_str())).collect();
+        let list = List::new(items).highlight_style(theme.hlrow);
+        f.render_stateful_widget(list, layout[0], &mut self.state);
+
+        let prompt = "> ";
+        let filter = Paragraph::new(format!("{}{}", prompt, self.filter.value())).style(theme.input);
+        f.render_widget(filter, layout[1]);
+        f.set_cursor(layout[1].x + (prompt.len() + self.filter.visual_cursor()) as u16, layout[1].y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tui_input::InputRequest;
+
+    use super::Picker;
+
+    #[test]
+    fn test_filter() {
+        let items = vec![("SELECT * FROM users".to_owned(), 1), ("DELETE FROM users".to_owned(), 2), ("SELECT 1".to_owned(), 3)];
+        let mut picker = Picker::new("history", items);
+        assert_eq!(picker.selected(), Some(&1));
+        "users sel".chars().for_each(|c| picker.handle(InputRequest::InsertChar(c)));
+        assert_eq!(picker.selected(), Some(&1));
+        picker.next();
+        assert_eq!(picker.selected(), Some(&1));
+        picker.handle(InputRequest::InsertChar('x'));
+        assert_eq!(picker.selected(), None);
+    }
+}
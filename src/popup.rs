@@ -0,0 +1,62 @@
+use ratatui::{prelude::{Backend, Direction, Constraint, Layout, Rect}, Frame, widgets::{Block, Borders, Paragraph, Clear, Wrap}};
+
+use super::theme::Theme;
+
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(popup_layout[1])[1]
+}
+
+/// A yes/no question about an action that is waiting to be done
+pub struct Confirm<T> {
+    pub message: String,
+    pub action: T,
+}
+
+impl<T> Confirm<T> {
+    pub fn new(message: String, action: T) -> Self {
+        Confirm {
+            message,
+            action,
+        }
+    }
+
+    pub fn render<B: Backend>(&self, f: &mut Frame<B>, theme: &Theme) {
+        let block = Block::default().title("Confirm").title_style(theme.header).borders(Borders::ALL);
+        let mut area = centered_rect(50, 20, f.size());
+        // grow to fit long messages
+        let width = area.width.saturating_sub(2).max(1) as usize;
+        let lines = self.message.lines().map(|l| l.chars().count().div_ceil(width).max(1)).sum::<usize>() as u16 + 4;
+        if area.height < lines {
+            let height = lines.min(f.size().height);
+            area.y = area.y.saturating_sub((height - area.height) / 2);
+            area.height = height;
+        }
+        let text = Paragraph::new(format!("{}\n\n(y)es / (n)o", self.message))
+            .block(block)
+            .wrap(Wrap { trim: true });
+        f.render_widget(Clear, area);
+        f.render_widget(text, area);
+    }
+}
@@ -1,134 +1,588 @@
-use ratatui::{widgets::{TableState, Cell, Row, Table}, prelude::{Backend, Constraint, Rect}, style::{Style, Modifier}, Frame};
+use std::{collections::HashSet, ops::RangeInclusive, sync::Arc};
+
+use ratatui::{widgets::{TableState, Cell, Row, Table}, prelude::{Backend, Constraint, Rect}, Frame};
 use rusqlite::types::Value;
 
 use super::zoom::Zoom;
+use super::error::EditError;
+use super::error::{CountError, GrepError};
+use super::grep::{Grep, GrepOpts, Matcher};
+use super::pager::{Pager, PAGE};
+use super::sql::TableName;
+use super::theme::Theme;
+use super::format;
+use super::sort::Sort;
+use super::filter::{Condition, Filter};
+use super::visual::Selection;
 
+/// Columns are at least this wide, unless resized
+const MIN_WIDTH: u16 = 4;
+/// Columns fitted to their content are at most this wide
+const MAX_WIDTH: u16 = 40;
+/// Space between columns
+const SPACING: u16 = 1;
 
 #[derive(Clone)]
 pub struct DbTable {
     pub search: String,
     pub scheme: Vec<String>,
+    /// Selection and scroll, relative to the loaded rows
     pub state: TableState,
+    /// The loaded rows, entries[0] is row number window
     pub entries: Vec<Vec<Value>>,
+    pub window: usize,
+    count: Option<usize>,
+    /// Why counting the rows failed, it isn't tried again
+    uncounted: Option<Arc<rusqlite::Error>>,
+    pub pager: Option<Pager>,
+    /// The first column shown
     pub hstate: usize,
+    /// The column of the cursor
+    pub col: usize,
     pub hlen: usize,
+    /// The number of columns that fit on the screen, set when rendering
     pub hwidth: usize,
+    /// The width of every column
+    pub widths: Vec<u16>,
+    /// The width of the table the last time it was rendered
+    screen: u16,
     pub zoom: Zoom,
+    /// The table the rows came from, only set if the rows can be edited
+    pub table: Option<TableName>,
+    /// What the rowid is called in sql, a column can take the name rowid
+    pub rowid: &'static str,
+    pub rowids: Option<Vec<i64>>,
+    /// Cells changed in the open transaction, as (rowid, column)
+    pub dirty: HashSet<(i64, usize)>,
+    pub grep: Option<Grep>,
+    /// The order of the rows, the table is loaded again when it changes
+    pub sort: Sort,
+    /// The conditions the rows are filtered with, also loaded again when they change
+    pub filter: Filter,
+    /// The visual selection, only set in visual mode
+    pub visual: Option<Selection>,
+}
+
+/// Turn edited text back into a value, trying to keep the type of the old value.
+/// \N is NULL, as is an emptied number
+pub fn parse_value(text: &str, old: &Value) -> Value {
+    match old {
+        _ if text == "\\N" => Value::Null,
+        Value::Null | Value::Integer(_) | Value::Real(_) if text.is_empty() => Value::Null,
+        Value::Integer(_) => text.parse().map(Value::Integer).unwrap_or_else(|_| Value::Text(text.to_owned())),
+        Value::Real(_) => text.parse().map(Value::Real).unwrap_or_else(|_| Value::Text(text.to_owned())),
+        _ => Value::Text(text.to_owned()),
+    }
 }
 
-fn show(v: &Value) -> String {
-    match v {
-        Value::Null => "Null".to_string(),
-        Value::Integer(i) => i.to_string(),
-        Value::Real(f) => f.to_string(),
-        Value::Text(t) => t.to_string(),
-        Value::Blob(_b) => "Blob".to_string(),
+/// Add the cells of row number i that match
+fn grep_row(matcher: &Matcher, i: usize, row: &[Value], matches: &mut Vec<(usize, usize)>) {
+    for (j, value) in row.iter().enumerate() {
+        if matcher.is_match(&format::text(value)) {
+            matches.push((i, j));
+        }
     }
 }
 
 impl DbTable {
+    /// Create a table where all rows are already loaded
     pub fn new(search: String, scheme: Vec<String>, entries: Vec<Vec<Value>>) -> Self {
-        let hlen = if entries.is_empty() {
-            0
-        } else {
-            entries[0].len()
-        };
         let len = scheme.len();
-        DbTable {
+        let mut table = DbTable {
             search,
+            hlen: len,
             scheme,
             state: TableState::default(),
+            window: 0,
+            count: Some(entries.len()),
+            uncounted: None,
             entries,
+            pager: None,
             hstate: 0,
-            hlen,
+            col: 0,
             hwidth: usize::min(5, len),
+            widths: Vec::new(),
+            screen: 0,
             zoom: Zoom::new(100, 70, 5),
+            table: None,
+            rowid: "rowid",
+            rowids: None,
+            dirty: HashSet::new(),
+            grep: None,
+            sort: Sort::default(),
+            filter: Filter::default(),
+            visual: None,
+        };
+        table.fit_widths();
+        table
+    }
+
+    /// Create a table that loads rows from pager when they are needed
+    pub fn paged(search: String, pager: Pager) -> rusqlite::Result<Self> {
+        let scheme = pager.columns()?;
+        let mut table = DbTable::new(search, scheme, Vec::new());
+        table.count = None;
+        table.pager = Some(pager);
+        table.load(0)?;
+        table.fit_widths();
+        Ok(table)
+    }
+
+    /// Make the rows editable, the table needs to be paged with rowids selected as rowid
+    pub fn editable(mut self, table: TableName, rowid: &'static str) -> Self {
+        self.table = Some(table);
+        self.rowid = rowid;
+        self
+    }
+
+    /// Make sure the pages around row are loaded, the window is moved if needed
+    fn load(&mut self, row: usize) -> rusqlite::Result<()> {
+        let Some(ref pager) = self.pager else {
+            return Ok(());
+        };
+        let start = (row / PAGE).saturating_sub(1) * PAGE;
+        let end = (row / PAGE + 2) * PAGE;
+        let loaded = self.window + self.entries.len();
+        if self.window <= start && (end <= loaded || self.count == Some(loaded)) {
+            return Ok(());
         }
+
+        let (rowids, entries) = pager.fetch(start, end - start)?;
+        if entries.len() < end - start && (start == 0 || !entries.is_empty()) {
+            self.count = Some(start + entries.len());
+        }
+        let selected = self.selected();
+        let offset = self.window + self.state.offset();
+        self.window = start;
+        self.entries = entries;
+        self.rowids = pager.rowid().then_some(rowids);
+        *self.state.offset_mut() = offset.saturating_sub(start);
+        self.state.select(selected.and_then(|i| i.checked_sub(start)));
+        Ok(())
     }
-    pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i >= self.entries.len() - 1 {
-                    0
-                } else {
-                    i + 1
+
+    /// The number of rows, counted the first time it's needed
+    pub fn count(&mut self) -> Result<usize, CountError> {
+        if let Some(count) = self.count {
+            return Ok(count);
+        }
+        if let Some(ref err) = self.uncounted {
+            return Err(CountError::Failed(err.clone()));
+        }
+        let count = match self.pager {
+            Some(ref pager) => match pager.count() {
+                Ok(count) => count,
+                Err(err) => {
+                    let err = Arc::new(err);
+                    self.uncounted = Some(err.clone());
+                    return Err(CountError::Failed(err));
                 }
-            }
-            None => 0,
+            },
+            None => self.entries.len(),
         };
-        self.state.select(Some(i));
-    }
-    pub fn prev(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    // lets wrap around
-                    self.entries.len() - 1
-                } else {
-                    i - 1
+        self.count = Some(count);
+        Ok(count)
+    }
+
+    /// The index of row in entries, if it's loaded
+    pub fn loaded(&self, row: usize) -> Option<usize> {
+        row.checked_sub(self.window).filter(|i| *i < self.entries.len())
+    }
+
+    /// The selected row number
+    pub fn selected(&self) -> Option<usize> {
+        self.state.selected().map(|i| self.window + i)
+    }
+
+    pub fn selected_values(&self) -> Option<&Vec<Value>> {
+        self.entries.get(self.state.selected()?)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> rusqlite::Result<()> {
+        let i = self.selected().map_or(0, |i| i + 1);
+        self.load(i)?;
+        if self.loaded(i).is_some() {
+            self.set(i)
+        } else if self.count == Some(0) {
+            Ok(())
+        } else {
+            self.set(0)
+        }
+    }
+    pub fn prev(&mut self) -> Result<(), CountError> {
+        match self.selected() {
+            // lets wrap around
+            Some(0) => self.last(),
+            Some(i) => Ok(self.set(i - 1)?),
+            None => Ok(self.set(0)?),
+        }
+    }
+    pub fn set(&mut self, i: usize) -> rusqlite::Result<()> {
+        self.load(i)?;
+        self.state.select(Some(i - self.window));
+        Ok(())
+    }
+
+    pub fn first(&mut self) -> rusqlite::Result<()> {
+        self.set(0)
+    }
+
+    /// The cell that should be edited, as (row, column)
+    pub fn selected_cell(&self) -> Option<(usize, usize)> {
+        let row = self.selected()?;
+        if self.loaded(row).is_some() && self.col < self.scheme.len() {
+            Some((row, self.col))
+        } else {
+            None
+        }
+    }
+
+    /// Get the cell to edit, failing if the cell can't be edited
+    pub fn edit_cell(&self) -> Result<(usize, usize), EditError> {
+        let (row, col) = self.selected_cell().ok_or(EditError::NoSelection)?;
+        if self.rowids.is_none() {
+            return Err(EditError::NoRowid);
+        }
+        if let Value::Blob(_) = self.entries[row - self.window][col] {
+            return Err(EditError::Blob);
+        }
+        Ok((row, col))
+    }
+
+    /// Go through all rows, not only the loaded ones
+    pub fn for_each<F>(&self, mut f: F) -> rusqlite::Result<()>
+    where
+        F: FnMut(usize, &[Value]) {
+        match self.pager {
+            Some(ref pager) => pager.for_each(f),
+            None => {
+                for (i, row) in self.entries.iter().enumerate() {
+                    f(i, row);
                 }
+                Ok(())
             }
-            None => 0,
-        };
-        self.state.select(Some(i));
+        }
     }
-    pub fn set(&mut self, i: usize) {
-        self.state.select(Some(i));
+
+    /// Search all cells for pattern, returning the number of matches
+    pub fn grep(&mut self, pattern: &str, opts: GrepOpts) -> Result<usize, GrepError> {
+        let matcher = Matcher::new(pattern, opts)?;
+        let mut matches = Vec::new();
+        self.for_each(|i, row| grep_row(&matcher, i, row, &mut matches))?;
+        Ok(self.set_grep(pattern, opts, matches))
     }
 
-    pub fn first(&mut self) {
-        self.set(0);
+    /// Search only the loaded rows, quick enough to do while the pattern is typed
+    pub fn grep_loaded(&mut self, pattern: &str, opts: GrepOpts) -> Result<usize, GrepError> {
+        let matcher = Matcher::new(pattern, opts)?;
+        let mut matches = Vec::new();
+        for (i, row) in self.entries.iter().enumerate() {
+            grep_row(&matcher, self.window + i, row, &mut matches);
+        }
+        Ok(self.set_grep(pattern, opts, matches))
     }
 
-    pub fn grep(&mut self, ) {
+    fn set_grep(&mut self, pattern: &str, opts: GrepOpts, matches: Vec<(usize, usize)>) -> usize {
+        let len = matches.len();
+        self.grep = Some(Grep {
+            pattern: pattern.to_owned(),
+            opts,
+            matches,
+            current: None,
+        });
+        len
     }
-    
-    pub fn last(&mut self) {
-        self.set(self.entries.len() - 1);
+
+    pub fn next_match(&mut self) -> rusqlite::Result<()> {
+        let row = self.selected().unwrap_or(0);
+        match self.grep.as_mut().and_then(|g| g.next(row)) {
+            Some(m) => self.goto(m),
+            None => Ok(()),
+        }
+    }
+
+    pub fn prev_match(&mut self) -> rusqlite::Result<()> {
+        let row = self.selected().unwrap_or(0);
+        match self.grep.as_mut().and_then(|g| g.prev(row)) {
+            Some(m) => self.goto(m),
+            None => Ok(()),
+        }
+    }
+
+    /// Move the cursor to the cell
+    pub fn goto(&mut self, (row, col): (usize, usize)) -> rusqlite::Result<()> {
+        self.set(row)?;
+        self.col = col;
+        self.scroll_to_col();
+        Ok(())
+    }
+
+    pub fn last(&mut self) -> Result<(), CountError> {
+        let count = self.count()?;
+        if count > 0 {
+            self.set(count - 1)?;
+        }
+        Ok(())
+    }
+
+    /// The rows and columns of a visual selection that ends at the cursor
+    pub fn range(&self, visual: Selection) -> Option<(RangeInclusive<usize>, RangeInclusive<usize>)> {
+        let cursor = self.selected_cell()?;
+        Some((visual.rows(cursor), visual.columns(cursor, self.scheme.len())))
+    }
+
+    /// The rows and columns of the visual selection
+    pub fn selection(&self) -> Option<(RangeInclusive<usize>, RangeInclusive<usize>)> {
+        self.range(self.visual?)
+    }
+
+    /// Get rows that might not be loaded, with their rowids if the table has them
+    pub fn fetch(&self, rows: RangeInclusive<usize>) -> rusqlite::Result<(Vec<i64>, Vec<Vec<Value>>)> {
+        match self.pager {
+            Some(ref pager) => pager.fetch(*rows.start(), rows.count()),
+            None => {
+                let end = (*rows.end() + 1).min(self.entries.len());
+                Ok((Vec::new(), self.entries[*rows.start()..end].to_vec()))
+            }
+        }
+    }
+
+    /// Text for the status line, like "match 3/17 row 12/100"
+    pub fn status(&self) -> String {
+        let mut status = String::new();
+        if let Some((rows, cols)) = self.selection() {
+            status.push_str(&format!("selected {}x{} ", rows.count(), cols.count()));
+        }
+        if let Some(ref grep) = self.grep {
+            status.push_str(&grep.status());
+            status.push(' ');
+        }
+        // the rows are counted when nothing else is happening, or when going to the last one
+        let row = self.selected().map_or(0, |i| i + 1);
+        match self.count {
+            Some(count) => status.push_str(&format!("row {}/{}", row, count)),
+            None => status.push_str(&format!("row {}/?", row)),
+        }
+        if let Some(name) = self.scheme.get(self.col) {
+            status.push_str(&format!(" col {}", name));
+        }
+        status
+    }
+
+    /// Sort by the column of the cursor, or change its direction
+    pub fn sort_toggle(&mut self) {
+        if let Some(name) = self.scheme.get(self.col) {
+            self.sort.toggle(name);
+        }
+    }
+
+    /// Add the column of the cursor to the sort keys
+    pub fn sort_add(&mut self) {
+        if let Some(name) = self.scheme.get(self.col) {
+            self.sort.add(name);
+        }
+    }
+
+    /// The column name and value of the cell of the cursor
+    fn cursor_value(&self) -> Option<(&String, &Value)> {
+        let (row, col) = self.selected_cell()?;
+        Some((&self.scheme[col], &self.entries[row - self.window][col]))
+    }
+
+    /// Only show the rows with the value of the cell of the cursor
+    pub fn filter_value(&mut self) {
+        if let Some((name, value)) = self.cursor_value() {
+            let condition = Condition::is(name, value);
+            self.filter.add(condition);
+        }
+    }
+
+    /// Hide the rows with the value of the cell of the cursor
+    pub fn exclude_value(&mut self) {
+        if let Some((name, value)) = self.cursor_value() {
+            let condition = Condition::is_not(name, value);
+            self.filter.add(condition);
+        }
+    }
+
+    /// Fit the columns to the header and the loaded rows
+    pub fn fit_widths(&mut self) {
+        self.widths = self.scheme.iter().enumerate().map(|(j, name)| {
+            let cells = self.entries.iter()
+                .filter_map(|row| row.get(j))
+                .map(|v| format::show(v).lines().map(|l| l.chars().count()).max().unwrap_or(0));
+            // leave room for a sort arrow in the header
+            let width = cells.fold(name.chars().count() + 2, usize::max);
+            (width as u16).clamp(MIN_WIDTH, MAX_WIDTH)
+        }).collect();
+    }
+
+    /// The number of columns from start that fit in width, at least one
+    fn fit(&self, start: usize, width: u16) -> usize {
+        let mut used = 0;
+        let mut n = 0;
+        for w in self.widths.iter().skip(start) {
+            used += w + SPACING;
+            if n > 0 && used > width + SPACING {
+                break;
+            }
+            n += 1;
+        }
+        n
+    }
+
+    /// Scroll so the column of the cursor is shown
+    fn scroll_to_col(&mut self) {
+        if self.col < self.hstate {
+            self.hstate = self.col;
+        }
+        while self.col >= self.hstate + self.fit(self.hstate, self.screen) {
+            self.hstate += 1;
+        }
     }
 
     // maybe these 2 should wrap
     pub fn hnext(&mut self) {
-        self.hstate = usize::min(self.hstate + 1, self.hlen - self.hwidth)
+        self.col = usize::min(self.col + 1, self.hlen.saturating_sub(1));
+        self.scroll_to_col();
     }
 
     pub fn hprev(&mut self) {
-        if self.hstate > 0 {
-            self.hstate -= 1; 
-        }
+        self.col = self.col.saturating_sub(1);
+        self.scroll_to_col();
     }
     
-    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, rect: Rect) {
+    /// Make the column of the cursor wider, or narrower if delta is negative
+    pub fn resize(&mut self, delta: i16) {
+        if let Some(w) = self.widths.get_mut(self.col) {
+            *w = w.saturating_add_signed(delta).max(1);
+        }
+        self.scroll_to_col();
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, rect: Rect, theme: &Theme) {
+        self.screen = rect.width;
+        self.scroll_to_col();
+        self.hwidth = self.fit(self.hstate, rect.width);
+        let cursor = self.state.selected();
+        let visual = self.visual.zip(self.selected_cell());
+        let widths: Vec<u16> = self.widths.iter().skip(self.hstate).take(self.hwidth).copied().collect();
         let headers_cells = self.scheme
             .iter()
             .skip(self.hstate)
-            .map(|h| Cell::from(h.clone()).style(Style::default().add_modifier(Modifier::REVERSED)));
+            .take(self.hwidth)
+            .map(|h| match self.sort.arrow(h) {
+                Some(arrow) => Cell::from(format!("{} {}", h, arrow)),
+                None => Cell::from(h.clone()),
+            }.style(theme.header));
         let header_rows = Row::new(headers_cells)
             .height(1);
-        let selected_style = Style::default().add_modifier(Modifier::REVERSED);
-        let rows = self.entries.iter().map(|item| {
+        let rows = self.entries.iter().enumerate().map(|(i, item)| {
             let height = item
                 .iter()
                 // .take(4)
-                .map(|content| show(content).chars().filter(|c| *c == '\n').count())
+                .map(|content| format::show(content).chars().filter(|c| *c == '\n').count())
                 .max()
                 .unwrap_or(0)
                 + 1;
-            let cells = item.iter().skip(self.hstate).map(|c| Cell::from(show(c)));
+            let rowid = self.rowids.as_ref().map(|ids| ids[i]);
+            let cells = item.iter().enumerate().skip(self.hstate).zip(widths.iter()).map(|((j, c), w)| {
+                let text = format::show(c);
+                let cell = match format::is_numeric(c) {
+                    true => Cell::from(format::align_right(&text, *w as usize)),
+                    false => Cell::from(text),
+                };
+                if cursor == Some(i) && j == self.col {
+                    cell.style(theme.hlcell)
+                } else if visual.is_some_and(|(v, at)| v.contains(at, (self.window + i, j))) {
+                    cell.style(theme.visual)
+                } else if self.grep.as_ref().is_some_and(|g| g.is_match(self.window + i, j)) {
+                    cell.style(theme.matched)
+                } else if rowid.is_some_and(|id| self.dirty.contains(&(id, j))) {
+                    cell.style(theme.dirty)
+                } else {
+                    cell.style(theme.value(c))
+                }
+            });
             Row::new(cells).height(height as u16)
         });
-        let mut cons = Vec::new();
-        let width = 100/self.hwidth as u16;
-        for _ in 0..self.hwidth {
-            cons.push(Constraint::Percentage(width));
-        }
+        let cons: Vec<Constraint> = widths.iter().map(|w| Constraint::Length(*w)).collect();
 
         let t = Table::new(rows)
             // .block(Block::default())
             .header(header_rows)
-            .highlight_style(selected_style)
+            .column_spacing(SPACING)
+            .highlight_style(theme.hlrow)
             // We need to loop over rows etc depending on f.size and min(row.length, maxlength)
             .widths(&cons[..]);
         f.render_stateful_widget(t, rect, &mut self.state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use rusqlite::{Connection, types::Value};
+
+    use super::{parse_value, DbTable};
+    use crate::grep::GrepOpts;
+    use crate::pager::{Pager, PAGE};
+
+    #[test]
+    fn test_paging() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t(x INTEGER);
+            WITH RECURSIVE c(x) AS (SELECT 0 UNION ALL SELECT x + 1 FROM c WHERE x < 999)
+            INSERT INTO t SELECT x FROM c;").unwrap();
+        let pager = Pager::new(Rc::new(conn), "SELECT rowid, * FROM t", true).unwrap();
+        let mut table = DbTable::paged("SELECT * FROM t".to_owned(), pager).unwrap();
+        assert!(table.entries.len() <= 2 * PAGE);
+        assert!(table.status().starts_with("row 0/? "));
+
+        table.last().unwrap();
+        assert!(table.status().starts_with("row 1000/1000 "));
+        assert_eq!(table.selected(), Some(999));
+        assert_eq!(table.selected_values(), Some(&vec![Value::Integer(999)]));
+
+        table.next().unwrap();
+        assert_eq!(table.selected(), Some(0));
+        table.prev().unwrap();
+        assert_eq!(table.selected(), Some(999));
+
+        for _ in 0..PAGE {
+            table.prev().unwrap();
+        }
+        assert_eq!(table.selected_values(), Some(&vec![Value::Integer(999 - PAGE as i64)]));
+        assert!(table.entries.len() <= 3 * PAGE);
+
+        // typing a search only looks at the loaded rows
+        assert!(table.grep_loaded("5", GrepOpts::default()).unwrap() < 271);
+        assert_eq!(table.grep("5", GrepOpts::default()).unwrap(), 271);
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("\\N", &Value::Text("a".to_owned())), Value::Null);
+        assert_eq!(parse_value("", &Value::Integer(1)), Value::Null);
+        assert_eq!(parse_value("", &Value::Text("a".to_owned())), Value::Text(String::new()));
+        assert_eq!(parse_value("2", &Value::Integer(1)), Value::Integer(2));
+    }
+
+    #[test]
+    fn test_widths() {
+        let scheme = vec!["id".to_owned(), "name".to_owned(), "text".to_owned()];
+        let entries = vec![vec![Value::Integer(1), Value::Text("abcdefgh".to_owned()), Value::Text("x".repeat(100))]];
+        let mut table = DbTable::new(String::new(), scheme, entries);
+        assert_eq!(table.widths, [4, 8, 40]);
+        table.screen = 20;
+        assert_eq!(table.fit(0, 20), 2);
+        table.hnext();
+        table.hnext();
+        table.hnext();
+        assert_eq!(table.hstate, 2);
+        table.resize(-2);
+        assert_eq!(table.widths[2], 38);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::ops::Range;
+use std::path::Path;
+use std::str::CharIndices;
+
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use super::export::sql_literal;
+use super::sql::skip_quoted;
+
+/// A query kept in the config to run by name
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedQuery {
+    pub name: String,
+    /// Parameters like ? or :name are asked for before running it
+    pub sql: String,
+    /// A glob of the database files the query is for, like *.db, for every database if None
+    #[serde(default)]
+    pub database: Option<String>,
+}
+
+/// Match text against a glob where * is any text and ? any char
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|i| glob(rest, &text[i..])),
+        Some(('?', rest)) => !text.is_empty() && glob(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+impl SavedQuery {
+    /// The query can be run on the database at path, the glob matches its path or file name
+    pub fn applies(&self, path: Option<&str>) -> bool {
+        let Some(ref pattern) = self.database else {
+            return true;
+        };
+        let Some(path) = path else {
+            return false;
+        };
+        let pattern: Vec<char> = pattern.chars().collect();
+        let name = Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or(path);
+        glob(&pattern, &path.chars().collect::<Vec<_>>()) || glob(&pattern, &name.chars().collect::<Vec<_>>())
+    }
+}
+
+/// The end of the name of the parameter that starts at i, a number after ?
+fn name_end(chars: &mut Peekable<CharIndices>, i: usize, digits: bool) -> usize {
+    let mut end = i + 1;
+    while let Some(&(j, c)) = chars.peek() {
+        let ok = if digits { c.is_ascii_digit() } else { c.is_alphanumeric() || c == '_' };
+        if !ok {
+            break;
+        }
+        end = j + c.len_utf8();
+        chars.next();
+    }
+    end
+}
+
+/// The parameters in sql as (bytes, name), a ? is numbered like sqlite does
+fn scan(sql: &str) -> Vec<(Range<usize>, String)> {
+    let mut found = Vec::new();
+    let mut largest = 0;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // strings, quoted names and comments can't have parameters
+            '\'' | '"' | '`' | '[' => skip_quoted(&mut chars, c),
+            '-' if chars.peek().is_some_and(|&(_, d)| d == '-') => {
+                for (_, d) in chars.by_ref() {
+                    if d == '\n' {
+                        break;
+                    }
+                }
+            }
+            '?' => {
+                let end = name_end(&mut chars, i, true);
+                let n = sql[i + 1..end].parse().unwrap_or(largest + 1);
+                largest = largest.max(n);
+                found.push((i..end, format!("?{}", n)));
+            }
+            ':' | '@' | '$' => {
+                let end = name_end(&mut chars, i, false);
+                if end > i + 1 {
+                    found.push((i..end, sql[i..end].to_owned()));
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// A saved query that is run once its parameters have values
+pub struct Run {
+    pub query: SavedQuery,
+    /// Every parameter once, in order
+    names: Vec<String>,
+    values: HashMap<String, Value>,
+}
+
+impl Run {
+    pub fn new(query: SavedQuery) -> Self {
+        let mut names: Vec<String> = Vec::new();
+        for (_, name) in scan(&query.sql) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        Run {
+            query,
+            names,
+            values: HashMap::new(),
+        }
+    }
+
+    /// The parameter to ask for next, None once all have a value
+    pub fn next(&self) -> Option<&str> {
+        self.names.iter().find(|n| !self.values.contains_key(*n)).map(|n| n.as_str())
+    }
+
+    /// Give the next parameter a value
+    pub fn set(&mut self, value: Value) {
+        if let Some(name) = self.next().map(|n| n.to_owned()) {
+            self.values.insert(name, value);
+        }
+    }
+
+    /// The sql with the values in place of the parameters
+    pub fn sql(&self) -> String {
+        let mut sql = self.query.sql.clone();
+        // from the end so the ranges before stay the same
+        for (range, name) in scan(&self.query.sql).into_iter().rev() {
+            if let Some(value) = self.values.get(&name) {
+                sql.replace_range(range, &sql_literal(value));
+            }
+        }
+        sql
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::types::Value;
+
+    use super::{Run, SavedQuery};
+
+    #[test]
+    fn test_run() {
+        let query = SavedQuery {
+            name: "orders".to_owned(),
+            sql: "SELECT ':no', ? FROM orders WHERE user = :user AND total > ?5 OR user = :user -- ?".to_owned(),
+            database: Some("shop*.db".to_owned()),
+        };
+        assert!(query.applies(Some("/srv/shop-2024.db")));
+        assert!(!query.applies(Some("/srv/shop.sqlite")));
+        let mut run = Run::new(query);
+        assert_eq!(run.next(), Some("?1"));
+        run.set(Value::Text("it's".to_owned()));
+        assert_eq!(run.next(), Some(":user"));
+        run.set(Value::Integer(7));
+        assert_eq!(run.next(), Some("?5"));
+        run.set(Value::Real(1.5));
+        assert_eq!(run.next(), None);
+        assert_eq!(run.sql(), "SELECT ':no', 'it''s' FROM orders WHERE user = 7 AND total > 1.5 OR user = 7 -- ?");
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashSet;
+
+use ratatui::{prelude::{Backend, Constraint, Direction, Layout, Rect}, Frame, widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap}};
+use rusqlite::Connection;
+
+use super::sql::{quote, TableName};
+use super::theme::Theme;
+
+#[derive(Clone)]
+pub struct Column {
+    pub name: String,
+    pub kind: String,
+    pub notnull: bool,
+    pub default: Option<String>,
+    /// Position in the primary key, 0 if not part of it
+    pub pk: i64,
+}
+
+#[derive(Clone)]
+pub struct ForeignKey {
+    /// The columns of a foreign key with several columns have the same id
+    pub id: i64,
+    pub table: String,
+    pub from: String,
+    pub to: Option<String>,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+#[derive(Clone)]
+pub struct Index {
+    pub name: String,
+    pub unique: bool,
+    pub columns: Vec<String>,
+}
+
+/// A table, view, index or trigger from sqlite_master
+pub struct SchemaObject {
+    pub kind: String,
+    /// The attached database the object is in, None for main
+    pub schema: Option<String>,
+    pub name: String,
+    pub table: String,
+    pub sql: Option<String>,
+    pub columns: Vec<Column>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub indexes: Vec<Index>,
+    /// Why the columns couldn't be read, like for a view of a dropped table
+    pub error: Option<String>,
+}
+
+pub fn table_info(conn: &Connection, table: &TableName) -> rusqlite::Result<Vec<Column>> {
+    let mut stmt = conn.prepare(&table.pragma("table_info"))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Column {
+            name: row.get(1)?,
+            kind: row.get(2)?,
+            notnull: row.get(3)?,
+            default: row.get(4)?,
+            pk: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The name the rowid of table can be selected by, columns named rowid, _rowid_ or oid hide it.
+/// None if they are all taken or the table is WITHOUT ROWID
+pub fn rowid_alias(conn: &Connection, table: &TableName) -> rusqlite::Result<Option<&'static str>> {
+    let columns = table_info(conn, table)?;
+    let alias = ["rowid", "_rowid_", "oid"].iter().copied().find(|alias| !columns.iter().any(|c| c.name.eq_ignore_ascii_case(alias)));
+    Ok(alias.filter(|alias| conn.prepare(&format!("SELECT {} FROM {}", alias, table.sql())).is_ok()))
+}
+
+pub fn foreign_keys(conn: &Connection, table: &TableName) -> rusqlite::Result<Vec<ForeignKey>> {
+    let mut stmt = conn.prepare(&table.pragma("foreign_key_list"))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ForeignKey {
+            id: row.get(0)?,
+            table: row.get(2)?,
+            from: row.get(3)?,
+            to: row.get(4)?,
+            on_update: row.get(5)?,
+            on_delete: row.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn index_columns(conn: &Connection, index: &TableName) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&index.pragma("index_info"))?;
+    let rows = stmt.query_map([], |row| {
+        let name: Option<String> = row.get(2)?;
+        // expressions in an index don't have a name
+        Ok(name.unwrap_or_else(|| "<expr>".to_owned()))
+    })?;
+    rows.collect()
+}
+
+pub fn indexes(conn: &Connection, table: &TableName) -> rusqlite::Result<Vec<Index>> {
+    let list = {
+        let mut stmt = conn.prepare(&table.pragma("index_list"))?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, bool>(2)?)))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    list.into_iter().map(|(name, unique)| {
+        Ok(Index {
+            columns: index_columns(conn, &table.sibling(&name))?,
+            name,
+            unique,
+        })
+    }).collect()
+}
+
+impl SchemaObject {
+    pub fn table_name(&self) -> TableName {
+        TableName::new(self.schema.as_deref(), &self.name)
+    }
+
+    pub fn is_openable(&self) -> bool {
+        self.kind == "table" || self.kind == "view"
+    }
+
+    /// Lines shown under the object when it's expanded
+    fn details(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for col in self.columns.iter() {
+            let mut line = format!("{} {}", col.name, col.kind).trim_end().to_owned();
+            if col.pk > 0 {
+                line.push_str(" PK");
+            }
+            if col.notnull {
+                line.push_str(" NOT NULL");
+            }
+            if let Some(ref default) = col.default {
+                line.push_str(&format!(" DEFAULT {}", default));
+            }
+            lines.push(line);
+        }
+        for fk in self.foreign_keys.iter() {
+            lines.push(format!("fk {} -> {}({}) ON DELETE {}", fk.from, fk.table,
+                    fk.to.as_deref().unwrap_or("rowid"), fk.on_delete));
+        }
+        for index in self.indexes.iter() {
+            let unique = if index.unique { "unique " } else { "" };
+            lines.push(format!("{}index {} ({})", unique, index.name, index.columns.join(", ")));
+        }
+        if let Some(ref error) = self.error {
+            lines.push(format!("error: {}", error));
+        }
+        lines
+    }
+
+    /// Read the columns, foreign keys and indexes of the object
+    fn load_details(&mut self, conn: &Connection) -> rusqlite::Result<()> {
+        let name = self.table_name();
+        match self.kind.as_str() {
+            "table" => {
+                self.columns = table_info(conn, &name)?;
+                self.foreign_keys = foreign_keys(conn, &name)?;
+                self.indexes = indexes(conn, &name)?;
+            }
+            "view" => {
+                self.columns = table_info(conn, &name)?;
+            }
+            "index" => {
+                self.columns = index_columns(conn, &name)?.into_iter().map(|name| Column {
+                    name,
+                    kind: String::new(),
+                    notnull: false,
+                    default: None,
+                    pk: 0,
+                }).collect();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// A node in the flattened tree, object is None for group headers and details
+struct Node {
+    depth: usize,
+    text: String,
+    object: Option<usize>,
+}
+
+pub struct Schema {
+    pub objects: Vec<SchemaObject>,
+    expanded: HashSet<usize>,
+    state: ListState,
+}
+
+const KINDS: [(&str, &str); 4] = [("table", "Tables"), ("view", "Views"), ("index", "Indexes"), ("trigger", "Triggers")];
+
+impl Schema {
+    pub fn load(conn: &Connection) -> rusqlite::Result<Self> {
+        let databases = {
+            let mut stmt = conn.prepare("PRAGMA database_list")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+
+        let mut objects = Vec::new();
+        for db in databases {
+            let schema = if db == "main" { None } else { Some(db) };
+            objects.extend(Self::load_objects(conn, schema)?);
+        }
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Ok(Schema {
+            objects,
+            expanded: HashSet::new(),
+            state,
+        })
+    }
+
+    fn load_objects(conn: &Connection, schema: Option<String>) -> rusqlite::Result<Vec<SchemaObject>> {
+        let master = match schema {
+            Some(ref schema) => format!("{}.sqlite_master", quote(schema)),
+            None => "sqlite_master".to_owned(),
+        };
+        let mut stmt = conn.prepare(&format!("SELECT type, name, tbl_name, sql FROM {} WHERE name NOT LIKE 'sqlite_%' ORDER BY name", master))?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+
+        let mut objects = Vec::new();
+        for row in rows {
+            let (kind, name, table, sql): (String, String, String, Option<String>) = row?;
+            let mut obj = SchemaObject {
+                kind,
+                schema: schema.clone(),
+                name,
+                table,
+                sql,
+                columns: Vec::new(),
+                foreign_keys: Vec::new(),
+                indexes: Vec::new(),
+                error: None,
+            };
+            // one object that can't be read is still listed, with the error
+            if let Err(err) = obj.load_details(conn) {
+                obj.error = Some(err.to_string());
+            }
+            objects.push(obj);
+        }
+        Ok(objects)
+    }
+
+    fn nodes(&self) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        for (kind, header) in KINDS.iter() {
+            nodes.push(Node { depth: 0, text: header.to_string(), object: None });
+            for (i, obj) in self.objects.iter().enumerate().filter(|(_, o)| o.kind == *kind) {
+                let text = if obj.kind == "index" || obj.kind == "trigger" {
+                    format!("{} on {}", obj.table_name(), obj.table)
+                } else {
+                    obj.table_name().to_string()
+                };
+                nodes.push(Node { depth: 1, text, object: Some(i) });
+                if self.expanded.contains(&i) {
+                    for line in obj.details() {
+                        nodes.push(Node { depth: 2, text: line, object: None });
+                    }
+                }
+            }
+        }
+        nodes
+    }
+
+    /// The object under the cursor, or the object a detail line belongs to
+    pub fn selected(&self) -> Option<&SchemaObject> {
+        self.selected_index().map(|i| &self.objects[i])
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        let nodes = self.nodes();
+        let i = self.state.selected()?;
+        nodes.get(..=i)?.iter().rev().take_while(|n| n.depth > 0).find_map(|n| n.object)
+    }
+
+    pub fn next(&mut self) {
+        let len = self.nodes().len();
+        let i = self.state.selected().map_or(0, |i| (i + 1) % len);
+        self.state.select(Some(i));
+    }
+
+    pub fn prev(&mut self) {
+        let len = self.nodes().len();
+        let i = match self.state.selected() {
+            Some(0) | None => len - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn expand(&mut self) {
+        if let Some(i) = self.selected_index() {
+            self.expanded.insert(i);
+        }
+    }
+
+    /// Collapse the object and move the cursor to it
+    pub fn collapse(&mut self) {
+        if let Some(i) = self.selected_index() {
+            self.expanded.remove(&i);
+            let pos = self.nodes().iter().position(|n| n.object == Some(i));
+            self.state.select(pos);
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        match self.selected_index() {
+            Some(i) if self.expanded.contains(&i) => self.collapse(),
+            Some(_) => self.expand(),
+            None => {}
+        }
+    }
+
+    pub fn render<B: Backend>(&mut self, f: &mut Frame<B>, rect: Rect, theme: &Theme) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(rect);
+
+        let items: Vec<ListItem> = self.nodes().into_iter().map(|n| {
+            let item = ListItem::new(format!("{}{}", "  ".repeat(n.depth), n.text));
+            if n.depth == 0 {
+                item.style(theme.group)
+            } else {
+                item
+            }
+        }).collect();
+        let list = List::new(items)
+            .block(Block::default().title("Schema").borders(Borders::ALL))
+            .highlight_style(theme.hlrow);
+        f.render_stateful_widget(list, layout[0], &mut self.state);
+
+        let sql = self.selected()
+            .and_then(|o| o.sql.clone())
+            .unwrap_or_default();
+        let text = Paragraph::new(sql)
+            .block(Block::default().title("Sql").borders(Borders::ALL))
+            .wrap(Wrap { trim: false });
+        f.render_widget(text, layout[1]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::Schema;
+
+    #[test]
+    fn test_broken_view() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t(a); CREATE TABLE u(b); CREATE VIEW v AS SELECT a FROM t; DROP TABLE t").unwrap();
+        let objects = Schema::load_objects(&conn, None).unwrap();
+        let names: Vec<&str> = objects.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, ["u", "v"]);
+        assert_eq!(objects[0].columns[0].name, "b");
+        assert!(objects[1].error.is_some());
+        assert!(objects[1].sql.is_some());
+    }
+}
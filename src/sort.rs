@@ -0,0 +1,93 @@
+use super::sql::quote;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SortKey {
+    pub column: String,
+    pub desc: bool,
+}
+
+/// The columns a table is ordered by, the first key is the primary one
+#[derive(Clone, Default, Debug)]
+pub struct Sort {
+    pub keys: Vec<SortKey>,
+}
+
+impl Sort {
+    fn position(&self, column: &str) -> Option<usize> {
+        self.keys.iter().position(|k| k.column == column)
+    }
+
+    /// Flip a key from ascending to descending, and remove it after that
+    fn cycle(&mut self, i: usize) {
+        if self.keys[i].desc {
+            self.keys.remove(i);
+        } else {
+            self.keys[i].desc = true;
+        }
+    }
+
+    /// Sort by column only, cycling ascending, descending and unsorted
+    pub fn toggle(&mut self, column: &str) {
+        match self.position(column) {
+            Some(0) if self.keys.len() == 1 => self.cycle(0),
+            _ => {
+                self.keys = vec![SortKey { column: column.to_owned(), desc: false }];
+            }
+        }
+    }
+
+    /// Add column as the last key, or cycle it if it's already a key
+    pub fn add(&mut self, column: &str) {
+        match self.position(column) {
+            Some(i) => self.cycle(i),
+            None => self.keys.push(SortKey { column: column.to_owned(), desc: false }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The ORDER BY clause, empty if nothing is sorted
+    pub fn order_by(&self) -> String {
+        if self.keys.is_empty() {
+            return String::new();
+        }
+        let keys: Vec<String> = self.keys.iter()
+            .map(|k| format!("{} {}", quote(&k.column), if k.desc { "DESC" } else { "ASC" }))
+            .collect();
+        format!(" ORDER BY {}", keys.join(", "))
+    }
+
+    /// The arrow shown in the header of column, numbered if there are several keys
+    pub fn arrow(&self, column: &str) -> Option<String> {
+        let i = self.position(column)?;
+        let arrow = if self.keys[i].desc { "▼" } else { "▲" };
+        match self.keys.len() {
+            1 => Some(arrow.to_owned()),
+            _ => Some(format!("{}{}", arrow, i + 1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sort;
+
+    #[test]
+    fn test_sort() {
+        let mut sort = Sort::default();
+        sort.toggle("a");
+        assert_eq!(sort.order_by(), " ORDER BY \"a\" ASC");
+        sort.toggle("a");
+        assert_eq!(sort.arrow("a").unwrap(), "▼");
+        sort.add("b");
+        assert_eq!(sort.order_by(), " ORDER BY \"a\" DESC, \"b\" ASC");
+        assert_eq!(sort.arrow("b").unwrap(), "▲2");
+        sort.toggle("b");
+        assert_eq!(sort.order_by(), " ORDER BY \"b\" ASC");
+        sort.toggle("b");
+        sort.toggle("b");
+        assert!(sort.is_empty());
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+/// Quote an identifier so it can be used as a table or column name
+pub fn quote(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Skip to the end of the string or quoted name opened by quote, a [name] ends with ]
+pub fn skip_quoted(chars: &mut impl Iterator<Item = (usize, char)>, quote: char) {
+    let end = if quote == '[' { ']' } else { quote };
+    for (_, c) in chars {
+        if c == end {
+            break;
+        }
+    }
+}
+
+/// A table name, in an attached database if schema is set
+#[derive(Clone, PartialEq, Eq)]
+pub struct TableName {
+    pub schema: Option<String>,
+    pub name: String,
+}
+
+impl TableName {
+    pub fn new(schema: Option<&str>, name: &str) -> Self {
+        TableName {
+            schema: schema.map(|s| s.to_owned()),
+            name: name.to_owned(),
+        }
+    }
+
+    /// The quoted name to use in sql
+    pub fn sql(&self) -> String {
+        match self.schema {
+            Some(ref schema) => format!("{}.{}", quote(schema), quote(&self.name)),
+            None => quote(&self.name),
+        }
+    }
+
+    /// A pragma about the table, like PRAGMA "db".table_info("t")
+    pub fn pragma(&self, pragma: &str) -> String {
+        match self.schema {
+            Some(ref schema) => format!("PRAGMA {}.{}({})", quote(schema), pragma, quote(&self.name)),
+            None => format!("PRAGMA {}({})", pragma, quote(&self.name)),
+        }
+    }
+
+    /// The same schema with another name, like an index on the table
+    pub fn sibling(&self, name: &str) -> Self {
+        TableName {
+            schema: self.schema.clone(),
+            name: name.to_owned(),
+        }
+    }
+}
+
+impl Display for TableName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.schema {
+            Some(ref schema) => write!(f, "{}.{}", schema, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Keywords of sqlite, highlighted in the sql editor
+pub const KEYWORDS: &[&str] = &[
+    "ABORT", "ACTION", "ADD", "AFTER", "ALL", "ALTER", "ALWAYS", "ANALYZE", "AND", "AS", "ASC",
+    "ATTACH", "AUTOINCREMENT", "BEFORE", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE", "CAST",
+    "CHECK", "COLLATE", "COLUMN", "COMMIT", "CONFLICT", "CONSTRAINT", "CREATE", "CROSS", "CURRENT",
+    "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP", "DATABASE", "DEFAULT", "DEFERRABLE",
+    "DEFERRED", "DELETE", "DESC", "DETACH", "DISTINCT", "DO", "DROP", "EACH", "ELSE", "END",
+    "ESCAPE", "EXCEPT", "EXCLUDE", "EXCLUSIVE", "EXISTS", "EXPLAIN", "FAIL", "FILTER", "FIRST",
+    "FOLLOWING", "FOR", "FOREIGN", "FROM", "FULL", "GENERATED", "GLOB", "GROUP", "GROUPS", "HAVING",
+    "IF", "IGNORE", "IMMEDIATE", "IN", "INDEX", "INDEXED", "INITIALLY", "INNER", "INSERT", "INSTEAD",
+    "INTERSECT", "INTO", "IS", "ISNULL", "JOIN", "KEY", "LAST", "LEFT", "LIKE", "LIMIT", "MATCH",
+    "MATERIALIZED", "NATURAL", "NO", "NOT", "NOTHING", "NOTNULL", "NULL", "NULLS", "OF", "OFFSET",
+    "ON", "OR", "ORDER", "OTHERS", "OUTER", "OVER", "PARTITION", "PLAN", "PRAGMA", "PRECEDING",
+    "PRIMARY", "QUERY", "RAISE", "RANGE", "RECURSIVE", "REFERENCES", "REGEXP", "REINDEX", "RELEASE",
+    "RENAME", "REPLACE", "RESTRICT", "RETURNING", "RIGHT", "ROLLBACK", "ROW", "ROWS", "SAVEPOINT",
+    "SELECT", "SET", "STRICT", "TABLE", "TEMP", "TEMPORARY", "THEN", "TIES", "TO", "TRANSACTION",
+    "TRIGGER", "UNBOUNDED", "UNION", "UNIQUE", "UPDATE", "USING", "VACUUM", "VALUES", "VIEW",
+    "VIRTUAL", "WHEN", "WHERE", "WINDOW", "WITH", "WITHOUT",
+];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+/// Built-in functions of sqlite, completed in the sql editor
+pub const FUNCTIONS: &[&str] = &[
+    "abs", "avg", "changes", "char", "coalesce", "count", "date", "datetime", "dense_rank",
+    "first_value", "format", "glob", "group_concat", "hex", "ifnull", "iif", "instr", "json",
+    "json_array", "json_each", "json_extract", "json_group_array", "json_group_object", "json_object",
+    "json_tree", "julianday", "lag", "last_insert_rowid", "last_value", "lead", "length", "like",
+    "likelihood", "lower", "ltrim", "max", "min", "nth_value", "ntile", "nullif", "printf", "quote",
+    "random", "randomblob", "rank", "replace", "round", "row_number", "rtrim", "sign", "strftime",
+    "substr", "sum", "time", "total", "total_changes", "trim", "typeof", "unhex", "unicode",
+    "unixepoch", "upper", "zeroblob",
+];
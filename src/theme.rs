@@ -0,0 +1,134 @@
+use ratatui::style::{Color, Modifier, Style};
+use rusqlite::types::Value;
+
+/// The styles every part of qb is drawn with
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// The first letter of a table tab
+    pub tab: Style,
+    /// The first letter of a view tab, the rest of the title is also italic
+    pub view: Style,
+    /// The selected tab
+    pub hltab: Style,
+    pub header: Style,
+    /// The selected row
+    pub hlrow: Style,
+    /// The cell of the cursor in the selected row
+    pub hlcell: Style,
+    pub null: Style,
+    pub number: Style,
+    pub blob: Style,
+    /// Cells edited in the open transaction
+    pub dirty: Style,
+    /// Cells matching the search
+    pub matched: Style,
+    pub error: Style,
+    pub input: Style,
+    /// Group headers in the schema browser
+    pub group: Style,
+    /// The filters shown above a table
+    pub chip: Style,
+    /// Cells in the visual selection
+    pub visual: Style,
+    /// Sql keywords in the editor
+    pub keyword: Style,
+    /// Sql strings in the editor
+    pub string: Style,
+    /// Sql comments in the editor
+    pub comment: Style,
+}
+
+/// Names of the built-in themes
+pub const THEMES: [&str; 3] = ["default", "light", "mono"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        let reversed = Style::default().add_modifier(Modifier::REVERSED);
+        Theme {
+            tab: Style::default().fg(Color::Yellow),
+            view: Style::default().fg(Color::Cyan),
+            hltab: reversed,
+            header: reversed,
+            hlrow: reversed,
+            hlcell: Style::default().fg(Color::Black).bg(Color::Cyan),
+            null: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            number: Style::default().fg(Color::LightBlue),
+            blob: Style::default().fg(Color::Magenta),
+            dirty: Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC),
+            matched: Style::default().fg(Color::Black).bg(Color::Yellow),
+            error: Style::default().fg(Color::Red),
+            input: Style::default(),
+            group: Style::default().add_modifier(Modifier::BOLD),
+            chip: Style::default().fg(Color::Black).bg(Color::Green),
+            visual: Style::default().bg(Color::DarkGray),
+            keyword: Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            string: Style::default().fg(Color::Green),
+            comment: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        }
+    }
+}
+
+impl Theme {
+    /// A built-in theme, see THEMES
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Theme::default()),
+            "light" => Some(Theme {
+                tab: Style::default().fg(Color::Blue),
+                view: Style::default().fg(Color::Magenta),
+                hltab: Style::default().fg(Color::White).bg(Color::Blue),
+                header: Style::default().fg(Color::Black).bg(Color::Gray).add_modifier(Modifier::BOLD),
+                hlrow: Style::default().bg(Color::LightCyan),
+                hlcell: Style::default().fg(Color::White).bg(Color::Blue),
+                null: Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                number: Style::default().fg(Color::Blue),
+                blob: Style::default().fg(Color::Magenta),
+                dirty: Style::default().fg(Color::Red).add_modifier(Modifier::ITALIC),
+                matched: Style::default().bg(Color::LightYellow),
+                error: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                input: Style::default().fg(Color::Black),
+                group: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+                chip: Style::default().fg(Color::Black).bg(Color::LightGreen),
+                visual: Style::default().bg(Color::LightBlue),
+                keyword: Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                string: Style::default().fg(Color::Green),
+                comment: Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            }),
+            "mono" => {
+                let plain = Style::default();
+                Some(Theme {
+                    tab: plain.add_modifier(Modifier::BOLD),
+                    view: plain.add_modifier(Modifier::BOLD),
+                    hltab: plain.add_modifier(Modifier::REVERSED),
+                    header: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    hlrow: plain.add_modifier(Modifier::REVERSED),
+                    hlcell: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    null: plain.add_modifier(Modifier::DIM),
+                    number: plain,
+                    blob: plain.add_modifier(Modifier::DIM),
+                    dirty: plain.add_modifier(Modifier::ITALIC),
+                    matched: plain.add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+                    error: plain.add_modifier(Modifier::BOLD),
+                    input: plain,
+                    group: plain.add_modifier(Modifier::BOLD),
+                    chip: plain.add_modifier(Modifier::REVERSED),
+                    visual: plain.add_modifier(Modifier::UNDERLINED),
+                    keyword: plain.add_modifier(Modifier::BOLD),
+                    string: plain.add_modifier(Modifier::ITALIC),
+                    comment: plain.add_modifier(Modifier::DIM),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The style of a cell with a value
+    pub fn value(&self, v: &Value) -> Style {
+        match v {
+            Value::Null => self.null,
+            Value::Integer(_) | Value::Real(_) => self.number,
+            Value::Blob(_) => self.blob,
+            Value::Text(_) => Style::default(),
+        }
+    }
+}
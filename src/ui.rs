@@ -1,12 +1,14 @@
 use ratatui::{
-    layout::Rect,
+    layout::{Rect, Alignment},
     backend::Backend,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Line},
     widgets::{Borders, Block, Tabs, Paragraph},
     Frame
 };
-use crate::Qb;
+use qb::{theme::Theme, filter::Filter};
+
+use crate::{Qb, TabKind};
 
 
 // fn show(v: &Value) -> String {
@@ -64,33 +66,54 @@ use crate::Qb;
 //         .split(popup_layout[1])[1]
 // }
 
-pub fn make_tabs<B: Backend>(qb: &Qb, f: &mut Frame<B>, rect: Rect) {
+pub fn make_tabs<B: Backend>(qb: &Qb, f: &mut Frame<B>, rect: Rect, theme: &Theme) {
     let dbs = qb.titles
         .iter()
-        .map(|t| {
-            let (first, rest) = t.split_at(1);
+        .zip(qb.kinds.iter())
+        .map(|(t, kind)| {
+            // the first char is highlighted, names can start with any char
+            let (first, rest) = t.split_at(t.char_indices().nth(1).map_or(t.len(), |(i, _)| i));
+            let (key, style) = match kind {
+                TabKind::View(_) => (theme.view, Style::default().add_modifier(Modifier::ITALIC)),
+                _ => (theme.tab, Style::default()),
+            };
             Line::from(vec![
             // Spans::from(vec![
-                Span::styled(first, Style::default().fg(Color::Yellow)),
-                Span::styled(rest, Style::default()),
+                Span::styled(first, key),
+                Span::styled(rest, style),
             ])
         }).collect();
     let ttabs = Tabs::new(dbs)
         .block(Block::default().borders(Borders::ALL))
         .select(qb.index)
-        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_style(theme.hltab)
         ;
     f.render_widget(ttabs, rect);
 }
 
-pub fn input_err<B: Backend>(error: &str, f: &mut Frame<B>, area: Rect) {
-    let input = Paragraph::new(error).style(Style::default().fg(Color::Red));
+/// The conditions of a filter, as chips above the table
+pub fn chips<B: Backend>(filter: &Filter, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+    let mut spans = Vec::new();
+    for condition in &filter.conditions {
+        spans.push(Span::styled(format!(" {} ", condition.chip()), theme.chip));
+        spans.push(Span::raw(" "));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+pub fn input_err<B: Backend>(error: &str, f: &mut Frame<B>, area: Rect, theme: &Theme) {
+    let input = Paragraph::new(error).style(theme.error);
     f.render_widget(input, area);
     let x = area.x;
     let y = area.y;
     f.set_cursor(x, y+1);
 }
 
+pub fn status<B: Backend>(text: &str, f: &mut Frame<B>, area: Rect) {
+    let status = Paragraph::new(text).alignment(Alignment::Right);
+    f.render_widget(status, area);
+}
+
 // pub fn InputBox<B: Backend>(qb: &mut Qb, f: &mut Frame<B>, input: InputType) -> Result<()>{
 //     let block = Block::default().title(input.show()).borders(Borders::ALL);
 //     let area = centered_rect(100, 20, f.size());
@@ -0,0 +1,64 @@
+use std::ops::RangeInclusive;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    /// Whole rows
+    Line,
+    /// The rows and columns between the anchor and the cursor
+    Block,
+}
+
+/// A visual selection, from where it started to the cursor
+#[derive(Clone, Copy, Debug)]
+pub struct Selection {
+    pub kind: Kind,
+    /// The cell the selection started at, as (row, column)
+    pub anchor: (usize, usize),
+}
+
+fn between(a: usize, b: usize) -> RangeInclusive<usize> {
+    a.min(b)..=a.max(b)
+}
+
+impl Selection {
+    pub fn new(kind: Kind, anchor: (usize, usize)) -> Self {
+        Selection { kind, anchor }
+    }
+
+    /// The selected rows when the cursor is at (row, column)
+    pub fn rows(&self, cursor: (usize, usize)) -> RangeInclusive<usize> {
+        between(self.anchor.0, cursor.0)
+    }
+
+    /// The selected columns when the cursor is at (row, column), out of ncols
+    pub fn columns(&self, cursor: (usize, usize), ncols: usize) -> RangeInclusive<usize> {
+        match self.kind {
+            Kind::Line => 0..=ncols.saturating_sub(1),
+            Kind::Block => between(self.anchor.1, cursor.1),
+        }
+    }
+
+    pub fn contains(&self, cursor: (usize, usize), cell: (usize, usize)) -> bool {
+        self.rows(cursor).contains(&cell.0) && match self.kind {
+            Kind::Line => true,
+            Kind::Block => between(self.anchor.1, cursor.1).contains(&cell.1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Kind, Selection};
+
+    #[test]
+    fn test_selection() {
+        let block = Selection::new(Kind::Block, (5, 3));
+        assert_eq!(block.rows((2, 1)), 2..=5);
+        assert_eq!(block.columns((2, 1), 10), 1..=3);
+        assert!(block.contains((2, 1), (4, 2)));
+        assert!(!block.contains((2, 1), (4, 4)));
+        let line = Selection::new(Kind::Line, (5, 3));
+        assert_eq!(line.columns((7, 3), 4), 0..=3);
+        assert!(line.contains((7, 3), (6, 0)));
+    }
+}
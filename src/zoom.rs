@@ -1,7 +1,9 @@
-use ratatui::{prelude::{Backend, Direction, Constraint, Layout, Rect}, Frame, widgets::{Block, Borders, Row, Cell, Table}, style::{Style, Modifier}};
-use rusqlite::types::Value;
+use ratatui::{prelude::{Backend, Constraint}, Frame, widgets::{Block, Borders, Row, Cell, Table}};
 
 use super::rows::DbTable;
+use super::popup::centered_rect;
+use super::theme::Theme;
+use super::format;
 
 #[derive(Clone)]
 pub struct Zoom {
@@ -11,52 +13,6 @@ pub struct Zoom {
     hstate: usize,
 }
 
-fn show_multiline(v: &Value, len: u16) -> String {
-    match v {
-        Value::Null => "Null".to_string(),
-        Value::Integer(i) => i.to_string(),
-        Value::Real(f) => f.to_string(),
-        Value::Text(text) => {
-            let mut result = String::new();
-            for (i, c) in text.chars().enumerate() {
-                result.push(c);
-                if (i + 1) % (len as usize) == 0 {
-                    result.push('\n');
-                }
-            }
-            result
-        }
-        Value::Blob(_b) => "Blob".to_string(),
-    }
-}
-
-// TODO: move this to generic thing
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Percentage((100 - percent_y) / 2),
-                Constraint::Percentage(percent_y),
-                Constraint::Percentage((100 - percent_y) / 2),
-            ]
-            .as_ref(),
-        )
-        .split(r);
-
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage((100 - percent_x) / 2),
-                Constraint::Percentage(percent_x),
-                Constraint::Percentage((100 - percent_x) / 2),
-            ]
-            .as_ref(),
-        )
-        .split(popup_layout[1])[1]
-}
-
 impl Zoom {
     pub fn new(width: u16, height: u16, zoom_width: usize) -> Self {
         Zoom {
@@ -67,16 +23,26 @@ impl Zoom {
         }
     }
 
+    /// The first column shown
+    pub fn column(&self) -> usize {
+        self.hstate
+    }
+
+    /// Show the columns from col, as far as there are enough columns to fill the zoom
+    pub fn focus(&mut self, col: usize, max: usize) {
+        self.hstate = usize::min(col, max.saturating_sub(self.zoom_width));
+    }
+
     pub fn zoom_out(&mut self, max: usize) {
         self.zoom_width = usize::min(max, self.zoom_width + 1);
     }
 
     pub fn zoom_in(&mut self) {
-        self.zoom_width = usize::max(1, self.zoom_width - 1);
+        self.zoom_width = usize::max(1, self.zoom_width.saturating_sub(1));
     }
 
     pub fn next(&mut self, max: usize) {
-        self.hstate = usize::min(self.hstate + 1, max - self.zoom_width); 
+        self.hstate = usize::min(self.hstate + 1, max.saturating_sub(self.zoom_width));
     }
 
     pub fn prev(&mut self) {
@@ -84,22 +50,26 @@ impl Zoom {
             self.hstate -= 1;
         }
     }
-    pub fn render<B: Backend>(&self, selected: &DbTable, f: &mut Frame<B>) { 
-        if let Some(row) = selected.state.selected() {
+    pub fn render<B: Backend>(&self, selected: &DbTable, f: &mut Frame<B>, theme: &Theme) { 
+        if let Some(row) = selected.selected_values() {
             let block = Block::default().title("Zoom").borders(Borders::ALL);
             let area = centered_rect(self.width_procent, self.height_procent, f.size());
 
             let headers_cells = selected.scheme
                 .iter()
                 .skip(self.hstate)
-                .map(|h| Cell::from(h.clone()).style(Style::default().add_modifier(Modifier::REVERSED)));
+                .map(|h| Cell::from(h.clone()).style(theme.header));
             let header_rows = Row::new(headers_cells)
                 .height(1);
-            let selected_style = Style::default().add_modifier(Modifier::REVERSED);
-            let row = &selected.entries[row];
-            let celllen = area.width / (self.zoom_width as u16);
+            // leave room for the border and the space between columns
+            let celllen = (area.width.saturating_sub(2) / (self.zoom_width as u16)).saturating_sub(1) as usize;
             let values = vec![Row::new(row.iter().skip(self.hstate).map(|item| {
-                Cell::from(show_multiline(item, celllen)) })).height(area.height-3)];
+                let text = format::multiline(item, celllen);
+                let text = match format::is_numeric(item) {
+                    true => format::align_right(&text, celllen),
+                    false => text,
+                };
+                Cell::from(text).style(theme.value(item)) })).height(area.height-3)];
             let mut cons = Vec::new();
             let width = 100/self.zoom_width as u16;
             for _ in 0..self.zoom_width {
@@ -109,7 +79,7 @@ impl Zoom {
             let t = Table::new(values)
                 .block(block)
                 .header(header_rows)
-                .highlight_style(selected_style)
+                .highlight_style(theme.hlrow)
                 .widths(&cons[..]);
             f.render_widget(t, area);
         }